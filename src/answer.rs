@@ -0,0 +1,146 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Answer {
+    Integer(i64),
+    Text(String),
+    // Rows of pixels, for letters that couldn't be read
+    Image(Vec<Vec<bool>>),
+    List(Vec<i64>),
+}
+
+impl Answer {
+    fn kind(&self) -> &str {
+        match self {
+            Answer::Integer(_) => "integer",
+            Answer::Text(_) => "text",
+            Answer::Image(_) => "image",
+            Answer::List(_) => "list",
+        }
+    }
+
+    // Fits on one line, for tables
+    pub fn summary(&self) -> String {
+        match self {
+            Answer::Image(rows) => format!("<{}x{} image>", rows.first().map_or(0, Vec::len), rows.len()),
+            _ => self.to_string(),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        match self {
+            Answer::Integer(n) => n.to_string(),
+            Answer::Text(s) => json_string(s),
+            Answer::Image(rows) => {
+                let rows: Vec<String> = rows.iter().map(|row| json_string(&render_row(row))).collect();
+                format!("[{}]", rows.join(","))
+            }
+            Answer::List(values) => format!("[{}]", join(values)),
+        }
+    }
+}
+
+fn render_row(row: &[bool]) -> String {
+    row.iter().map(|&it| if it { '#' } else { '.' }).collect()
+}
+
+fn join(values: &[i64]) -> String {
+    values.iter().map(|it| it.to_string()).collect::<Vec<_>>().join(",")
+}
+
+// The plain form, which is what the puzzle wants typed in
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Integer(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::Image(rows) => {
+                let rows: Vec<String> = rows.iter().map(|row| render_row(row)).collect();
+                write!(f, "{}", rows.join("\n"))
+            }
+            Answer::List(values) => write!(f, "{}", join(values)),
+        }
+    }
+}
+
+pub fn json_string(s: &str) -> String {
+    let mut res = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+// {"day":8,"part":2,"type":"text","answer":"ZLBJF","elapsed_ms":0.213}
+pub fn to_json(day: u32, part: u32, answer: &Answer, elapsed_ms: f64) -> String {
+    format!("{{\"day\":{},\"part\":{},\"type\":\"{}\",\"answer\":{},\"elapsed_ms\":{:.3}}}",
+            day, part, answer.kind(), answer.to_json(), elapsed_ms)
+}
+
+// Anything too big for an Integer is kept exactly as Text rather than wrapping
+macro_rules! from_integer {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Answer {
+            fn from(n: $t) -> Answer {
+                i64::try_from(n).map_or_else(|_| Answer::Text(n.to_string()), Answer::Integer)
+            }
+        })*
+    };
+}
+
+from_integer!(i32, i64, u32, u64, usize);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Answer {
+        Answer::Text(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Answer {
+        Answer::Text(s.to_string())
+    }
+}
+
+impl From<Vec<i64>> for Answer {
+    fn from(values: Vec<i64>) -> Answer {
+        Answer::List(values)
+    }
+}
+
+impl From<(i64, i64)> for Answer {
+    fn from((x, y): (i64, i64)) -> Answer {
+        Answer::List(vec![x, y])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn plain() {
+        assert_eq!("42", Answer::from(42u32).to_string());
+        assert_eq!(Answer::Integer(i64::MAX), Answer::from(i64::MAX as u64));
+        assert_eq!(Answer::Text("18446744073709551615".to_string()), Answer::from(u64::MAX));
+        assert_eq!("ZLBJF", Answer::from("ZLBJF").to_string());
+        assert_eq!("0,3,-1", Answer::from(vec![0, 3, -1]).to_string());
+        assert_eq!("#.\n.#", Answer::Image(vec![vec![true, false], vec![false, true]]).to_string());
+        assert_eq!("<2x1 image>", Answer::Image(vec![vec![true, false]]).summary());
+    }
+
+    #[test]
+    fn json() {
+        assert_eq!(r#"{"day":8,"part":2,"type":"text","answer":"a\"b\\c\n","elapsed_ms":1.500}"#, to_json(8, 2, &Answer::from("a\"b\\c\n"), 1.5));
+        assert_eq!(r#"{"day":9,"part":1,"type":"list","answer":[1,2],"elapsed_ms":0.000}"#, to_json(9, 1, &Answer::from(vec![1, 2]), 0.0));
+        assert_eq!(r###"["#.","##"]"###, Answer::Image(vec![vec![true, false], vec![true, true]]).to_json());
+    }
+}
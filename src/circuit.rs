@@ -0,0 +1,199 @@
+use crate::error::Error;
+use crate::intcode::{Computer, Program, Stream, Value};
+use crate::parallel;
+use crate::supervisor::{Status, Supervisor};
+use itertools::Itertools;
+use std::ops::Range;
+use std::str::FromStr;
+
+// The two circuits from day 7
+pub const SERIES: &str = "in -> A -> B -> C -> D -> E -> out";
+pub const FEEDBACK: &str = "in -> A -> B -> C -> D -> E -> A; E -> out";
+
+// The last signal out of five amps in a row, each given one of the phases
+pub fn run_amps_once(program: &Program, phases: &[Value]) -> Result<Value, Error> {
+    last_signal(SERIES, program, phases)
+}
+
+// The last signal out of five amps in a loop, where E also feeds back into A
+pub fn run_amps_looped(program: &Program, phases: &[Value]) -> Result<Value, Error> {
+    last_signal(FEEDBACK, program, phases)
+}
+
+fn last_signal(circuit: &str, program: &Program, phases: &[Value]) -> Result<Value, Error> {
+    let circuit: Circuit = circuit.parse().map_err(Error::Input)?;
+    circuit.run(program, phases)?
+        .pop()
+        .ok_or_else(|| Error::Unsolvable("No signal came out of the amps".to_string()))
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Node {
+    Input,
+    Output,
+    Amp(usize),
+}
+
+// Amplifiers wired together by statements like "in -> A -> B, C; B, C -> D -> out", separated by
+// semicolons or newlines. A comma list on the left feeds every amp on the right, "in" provides the
+// initial 0 signal and "out" collects the result.
+#[derive(Debug)]
+pub struct Circuit {
+    amps: Vec<String>,
+    wires: Vec<(Node, Node)>,
+}
+
+impl FromStr for Circuit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut amps: Vec<String> = Vec::new();
+        let mut wires = Vec::new();
+        for statement in s.split([';', '\n']).map(str::trim).filter(|it| !it.is_empty()) {
+            let groups: Vec<Vec<Node>> = statement.split("->")
+                .map(|group| group.split(',').map(|name| to_node(&mut amps, name.trim())).collect())
+                .collect::<Result<_, _>>()?;
+            if groups.len() < 2 {
+                return Err(format!("Expected at least one wire in '{}'", statement))
+            }
+            for pair in groups.windows(2) {
+                for (&from, &to) in pair[0].iter().cartesian_product(&pair[1]) {
+                    if from == Node::Output || to == Node::Input {
+                        return Err(format!("Wires go from in and to out: '{}'", statement))
+                    }
+                    wires.push((from, to));
+                }
+            }
+        }
+        if amps.is_empty() {
+            return Err("A circuit needs at least one amp".to_string())
+        }
+        Ok(Circuit { amps, wires })
+    }
+}
+
+fn to_node(amps: &mut Vec<String>, name: &str) -> Result<Node, String> {
+    match name {
+        "in" => Ok(Node::Input),
+        "out" => Ok(Node::Output),
+        _ if name.is_empty() || !name.chars().all(char::is_alphanumeric) => Err(format!("Invalid amp name '{}'", name)),
+        _ => {
+            let i = amps.iter().position(|it| it == name).unwrap_or_else(|| {
+                amps.push(name.to_string());
+                amps.len() - 1
+            });
+            Ok(Node::Amp(i))
+        }
+    }
+}
+
+impl Circuit {
+    // In the order they first appear in the description
+    pub fn amps(&self) -> &[String] {
+        &self.amps
+    }
+
+    // Phases are given in the order of amps. Returns everything sent to out, even if some amps end up
+    // waiting for input that never comes.
+    pub fn run(&self, program: &Program, phases: &[Value]) -> Result<Vec<Value>, Error> {
+        if self.amps.len() != phases.len() {
+            return Err(Error::Input(format!("Need one phase per amp, got {} for {}", phases.len(), self.amps.len())))
+        }
+        let mut supervisor = Supervisor::new();
+        for (name, &phase) in self.amps.iter().zip(phases) {
+            let mut comp = Computer::new(program.clone());
+            let input = Stream::new_wrapped();
+            input.borrow_mut().write(phase);
+            comp.set_input(Some(input));
+            comp.set_output(Some(Stream::new_wrapped()));
+            supervisor.add(name, comp);
+        }
+
+        let mut output = Vec::new();
+        self.deliver(Node::Input, &[0], supervisor.computers(), &mut output);
+        loop {
+            supervisor.round();
+            for (i, comp) in supervisor.computers().iter().enumerate() {
+                let values = comp.output().unwrap().borrow_mut().read_all();
+                self.deliver(Node::Amp(i), &values, supervisor.computers(), &mut output);
+            }
+            match supervisor.status() {
+                Status::Running => {}
+                Status::Halted => break,
+                Status::Deadlocked(blocked) => {
+                    for it in blocked {
+                        log::warn!("{}", it);
+                    }
+                    break
+                }
+                Status::Idle => unreachable!("Amps don't poll"),
+            }
+        }
+        Ok(output)
+    }
+
+    fn deliver(&self, from: Node, values: &[Value], computers: &[Computer], output: &mut Vec<Value>) {
+        for &(_, to) in self.wires.iter().filter(|&&(f, _)| f == from) {
+            match to {
+                Node::Amp(i) => computers[i].input().unwrap().borrow_mut().write_all(values),
+                Node::Output => output.extend_from_slice(values),
+                Node::Input => unreachable!("Nothing is wired into the input"),
+            }
+        }
+    }
+
+    // Tries every way of giving each amp a different phase from the range, each on its own machines.
+    // None if the range has too few phases to go round or no setting gets a signal out.
+    pub fn max_signal(&self, program: &Program, phases: Range<Value>, jobs: usize) -> Option<(Vec<Value>, Value)> {
+        let runs = parallel::map(jobs, phases.permutations(self.amps.len()).collect(), |phases| {
+            let signal = self.run(program, &phases).ok()?.pop()?;
+            Some((phases, signal))
+        });
+        runs.into_iter()
+            .flatten()
+            .max_by_key(|&(_, signal)| signal)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode;
+
+    #[test]
+    fn ex1() {
+        let program = intcode::parse_program(
+            "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,
+             27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5").unwrap();
+        assert_eq!(Ok(139629729), run_amps_looped(&program, &[9,8,7,6,5]));
+    }
+
+    #[test]
+    fn pt1ex1() {
+        let program = intcode::parse_program("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0").unwrap();
+        assert_eq!(Ok(43210), run_amps_once(&program, &[4,3,2,1,0]));
+        assert!(run_amps_once(&program, &[4,3,2]).is_err());
+        let circuit: Circuit = SERIES.parse().unwrap();
+        assert_eq!(Some((vec![4,3,2,1,0], 43210)), circuit.max_signal(&program, 0..5, 2));
+        assert_eq!(None, circuit.max_signal(&program, 0..4, 2));
+    }
+
+    #[test]
+    fn parse() {
+        let circuit: Circuit = "in -> A -> B, C\nB, C -> D -> out".parse().unwrap();
+        assert_eq!(vec!["A", "B", "C", "D"], circuit.amps());
+        assert_eq!(6, circuit.wires.len());
+        assert!("A".parse::<Circuit>().is_err());
+        assert!("in -> out".parse::<Circuit>().is_err());
+        assert!("out -> A".parse::<Circuit>().is_err());
+        assert!("in -> A -> -> out".parse::<Circuit>().is_err());
+    }
+
+    #[test]
+    fn fan_out() {
+        // Outputs its phase plus its input
+        let program = intcode::parse_program("3,11,3,12,1,11,12,13,4,13,99,0,0,0").unwrap();
+        let circuit: Circuit = "in -> A -> B, C -> out".parse().unwrap();
+        assert_eq!(Ok(vec![30, 200]), circuit.run(&program, &[10, 20, 190]));
+    }
+}
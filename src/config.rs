@@ -0,0 +1,39 @@
+use crate::params::Params;
+use std::path::PathBuf;
+use std::thread;
+
+// Everything about running a puzzle apart from its input, which the command line fills in. Solvers
+// are handed it by solution::execute, so puzzles running side by side don't share any settings.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    pub params: Params,
+    // Where Source::InputDir reads from, ./input if None
+    pub input_dir: Option<PathBuf>,
+    // Watch or play the puzzles that allow it instead of letting them solve themselves
+    pub interactive: bool,
+    // Animate the frames puzzles draw in the terminal
+    pub visualize: bool,
+    // Record the frames to this asciicast v2 file
+    pub record: Option<PathBuf>,
+    // Frames per second when animating or recording, 30 if None
+    pub fps: Option<f64>,
+    // Save an animated GIF here, for the puzzles that draw one
+    pub gif: Option<PathBuf>,
+    // Write the images and captures puzzles can export into this directory
+    pub export_dir: Option<PathBuf>,
+    // Threads to split work between, one per core if None
+    pub jobs: Option<usize>,
+}
+
+impl Config {
+    pub fn jobs(&self) -> usize {
+        self.jobs
+            .filter(|&it| it > 0)
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, |it| it.get()))
+    }
+
+    // Writes out what the puzzles run so far have saved up, like the frames for record
+    pub fn finish(&self) {
+        crate::visualize::finish(self)
+    }
+}
@@ -0,0 +1,194 @@
+use crate::intcode::{ComputerState, Engine, Program, Run, Value};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+pub const DIR: &str = "conformance";
+const MAX_STEPS: usize = 1_000_000;
+
+// Each file in the directory holds cases separated by blank lines, with lines starting with # as
+// comments:
+//   name: add
+//   program: 1,5,6,0,99,20,22
+//   input: 3,4          (optional)
+//   output: 42
+//   memory: 42,5,6,0    (optional, checked against the start of memory)
+//   state: waiting      (optional, defaults to halted)
+#[derive(Debug)]
+pub struct Case {
+    pub file: String,
+    pub name: String,
+    pub program: Program,
+    pub input: Vec<Value>,
+    pub output: Vec<Value>,
+    pub memory: Option<Program>,
+    pub state: ComputerState,
+}
+
+pub fn load(dir: &Path) -> Result<Vec<Case>, String> {
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .map_err(|e| format!("Could not read {}: {}", dir.display(), e))?
+        .filter_map(|it| it.ok().map(|it| it.path()))
+        .filter(|it| it.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    paths.sort();
+    let mut cases = Vec::new();
+    for path in paths {
+        let contents = std::fs::read_to_string(&path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        let file = path.file_name().unwrap().to_string_lossy().to_string();
+        cases.extend(parse_cases(&file, &contents)?);
+    }
+    Ok(cases)
+}
+
+fn parse_cases(file: &str, contents: &str) -> Result<Vec<Case>, String> {
+    let lines: Vec<&str> = contents.lines()
+        .map(str::trim)
+        .filter(|it| !it.starts_with('#'))
+        .collect();
+    lines.split(|it| it.is_empty())
+        .filter(|it| !it.is_empty())
+        .map(|block| parse_case(file, block))
+        .collect()
+}
+
+fn parse_case(file: &str, block: &[&str]) -> Result<Case, String> {
+    let mut case = Case {
+        file: file.to_string(),
+        name: String::new(),
+        program: Vec::new(),
+        input: Vec::new(),
+        output: Vec::new(),
+        memory: None,
+        state: ComputerState::Halted,
+    };
+    for line in block {
+        let (key, value) = line.split_once(':').ok_or_else(|| format!("{}: expected 'key: value' but got '{}'", file, line))?;
+        let value = value.trim();
+        let values = || parse_values(value).map_err(|e| format!("{}: {} in '{}'", file, e, line));
+        match key {
+            "name" => case.name = value.to_string(),
+            "program" => case.program = values()?,
+            "input" => case.input = values()?,
+            "output" => case.output = values()?,
+            "memory" => case.memory = Some(values()?),
+            "state" => case.state = match value {
+                "halted" => ComputerState::Halted,
+                "waiting" => ComputerState::WaitingOnInput,
+                _ => return Err(format!("{}: unknown state '{}'", file, value)),
+            },
+            _ => return Err(format!("{}: unknown key '{}'", file, key)),
+        }
+    }
+    if case.name.is_empty() || case.program.is_empty() {
+        return Err(format!("{}: every case needs a name and a program, near '{}'", file, block[0]))
+    }
+    Ok(case)
+}
+
+fn parse_values(s: &str) -> Result<Vec<Value>, String> {
+    if s.is_empty() {
+        return Ok(Vec::new())
+    }
+    s.split(',')
+        .map(|it| it.trim().parse().map_err(|e| format!("bad value '{}': {}", it.trim(), e)))
+        .collect()
+}
+
+// Turns a panicking engine into an error
+pub fn run_caught(engine: &dyn Engine, program: &Program, input: &[Value], max_steps: usize) -> Result<Run, String> {
+    panic::catch_unwind(AssertUnwindSafe(|| engine.run(program, input, max_steps)))
+        .map_err(|e| match e.downcast_ref::<String>() {
+            Some(message) => format!("panicked: {}", message),
+            None => format!("panicked: {}", e.downcast_ref::<&str>().unwrap_or(&"?")),
+        })
+}
+
+pub fn check(engine: &dyn Engine, case: &Case) -> Result<(), String> {
+    let run = run_caught(engine, &case.program, &case.input, MAX_STEPS)?;
+    match run.state {
+        Some(state) if state == case.state => {}
+        Some(state) => return Err(format!("expected state {:?} but was {:?}", case.state, state)),
+        None => return Err(format!("did not stop within {} steps", MAX_STEPS)),
+    }
+    if run.output != case.output {
+        return Err(format!("expected output {:?} but was {:?}", case.output, run.output))
+    }
+    if let Some(memory) = &case.memory {
+        let actual: Vec<Value> = (0..memory.len()).map(|i| run.memory.get(i).copied().unwrap_or(0)).collect();
+        if &actual != memory {
+            return Err(format!("expected memory {:?} but was {:?}", memory, actual))
+        }
+    }
+    Ok(())
+}
+
+// Returns the failing cases as (case, reason)
+pub fn run(engine: &dyn Engine, cases: &[Case]) -> Vec<(String, String)> {
+    cases.iter()
+        .filter_map(|case| check(engine, case).err().map(|e| (format!("{}: {}", case.file, case.name), e)))
+        .collect()
+}
+
+// Ok if every case in dir passed, Err if any failed or the cases couldn't be loaded
+pub fn report(engine: &dyn Engine, dir: &Path) -> Result<String, String> {
+    let cases = load(dir)?;
+    let failures = run(engine, &cases);
+    let mut s = String::new();
+    for (case, reason) in &failures {
+        s.push_str(&format!("FAIL {}: {}\n", case, reason));
+    }
+    s.push_str(&format!("{}: {}/{} cases passed", engine.name(), cases.len() - failures.len(), cases.len()));
+    if failures.is_empty() {
+        Ok(s)
+    } else {
+        Err(s)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::{Interpreter, Stepper};
+
+    #[test]
+    fn interpreter_conforms() {
+        let cases = load(&Path::new(env!("CARGO_MANIFEST_DIR")).join(DIR)).unwrap();
+        assert!(cases.len() > 100);
+        assert_eq!(Vec::<(String, String)>::new(), run(&Interpreter, &cases));
+        assert_eq!(Vec::<(String, String)>::new(), run(&Stepper, &cases));
+    }
+
+    struct Broken;
+
+    impl Engine for Broken {
+        fn name(&self) -> &str {
+            "broken"
+        }
+
+        fn run(&self, program: &Program, _input: &[Value], _max_steps: usize) -> Run {
+            if program[0] == 2 {
+                panic!("no multiplying")
+            }
+            Run { output: vec![1], memory: program.clone(), state: Some(ComputerState::Halted) }
+        }
+    }
+
+    #[test]
+    fn catches_failures() {
+        let cases = parse_cases("test", "# comment\nname: a\nprogram: 104,1,99\noutput: 1\n\nname: b\nprogram: 2,0,0,0,99\noutput:\n").unwrap();
+        let failures = run(&Broken, &cases);
+        assert_eq!(1, failures.len());
+        assert_eq!("test: b", failures[0].0);
+        assert!(failures[0].1.contains("no multiplying"));
+        assert!(parse_cases("test", "name: a\nprogram: 1,x").is_err());
+        assert!(parse_cases("test", "output: 1").is_err());
+    }
+
+    #[test]
+    fn reports_pass_or_fail() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(DIR);
+        assert!(report(&Interpreter, &dir).is_ok());
+        assert!(report(&Broken, &dir).unwrap_err().contains("FAIL"));
+        assert!(report(&Interpreter, &dir.join("missing")).is_err());
+    }
+}
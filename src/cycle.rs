@@ -0,0 +1,128 @@
+// Cycle detection for simulations that step a state deterministically. The state space must be finite
+// (or at least the states must repeat eventually) or these never return.
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Cycle {
+    // Steps before the first state that's part of the cycle
+    pub tail: usize,
+    pub period: usize,
+}
+
+impl Cycle {
+    // The step of the first state that has been seen before
+    pub fn first_repeat(&self) -> usize {
+        self.tail + self.period
+    }
+}
+
+pub fn floyd<S: Clone + Eq>(initial: S, step: impl Fn(&S) -> S) -> Cycle {
+    // The hare goes twice as fast so they meet somewhere in the cycle
+    let mut tortoise = step(&initial);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&step(&hare));
+    }
+    let period = find_period(&tortoise, &step);
+    Cycle { tail: find_tail(initial, &step, hare), period }
+}
+
+pub fn brent<S: Clone + Eq>(initial: S, step: impl Fn(&S) -> S) -> Cycle {
+    // The tortoise teleports to the hare at each power of two until the hare comes back around to it
+    let mut power = 1;
+    let mut period = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = step(&initial);
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = step(&hare);
+        period += 1;
+    }
+    let mut ahead = initial.clone();
+    for _ in 0..period {
+        ahead = step(&ahead);
+    }
+    Cycle { tail: find_tail(initial, &step, ahead), period }
+}
+
+// The state after n steps, skipping whole cycles once one is found
+pub fn fast_forward<S: Clone + Eq>(initial: S, step: impl Fn(&S) -> S, n: usize) -> S {
+    // Brent's search, stopping early if step n comes first
+    let mut power = 1;
+    let mut period = 0;
+    let mut tortoise = initial.clone();
+    let mut hare = initial;
+    let mut i = 0;
+    while i < n {
+        hare = step(&hare);
+        i += 1;
+        period += 1;
+        if hare == tortoise {
+            // The states repeat every period from here on
+            return (0..(n - i) % period).fold(hare, |state, _| step(&state))
+        }
+        if period == power {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+    }
+    hare
+}
+
+// Walks from the start and from a multiple of the period ahead until they meet at the start of the cycle
+fn find_tail<S: Eq>(initial: S, step: impl Fn(&S) -> S, ahead: S) -> usize {
+    let mut tail = 0;
+    let mut behind = initial;
+    let mut ahead = ahead;
+    while behind != ahead {
+        behind = step(&behind);
+        ahead = step(&ahead);
+        tail += 1;
+    }
+    tail
+}
+
+fn find_period<S: Eq>(start: &S, step: impl Fn(&S) -> S) -> usize {
+    let mut period = 1;
+    let mut state = step(start);
+    while state != *start {
+        state = step(&state);
+        period += 1;
+    }
+    period
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // 0 1 2 3 4 5 6 7 3 4 ...
+    fn rho(&x: &u32) -> u32 {
+        if x == 7 { 3 } else { x + 1 }
+    }
+
+    #[test]
+    fn detect() {
+        let expected = Cycle { tail: 3, period: 5 };
+        assert_eq!(expected, floyd(0, rho));
+        assert_eq!(expected, brent(0, rho));
+        assert_eq!(8, expected.first_repeat());
+        assert_eq!(Cycle { tail: 0, period: 5 }, brent(4, rho));
+        assert_eq!(Cycle { tail: 0, period: 1 }, floyd(1, |&x: &u32| x));
+    }
+
+    #[test]
+    fn skip_ahead() {
+        assert_eq!(2, fast_forward(0, rho, 2));
+        assert_eq!(7, fast_forward(0, rho, 7));
+        for n in 0..40 {
+            assert_eq!((0..n).fold(0, |x, _| rho(&x)), fast_forward(0, rho, n), "{}", n);
+        }
+        assert_eq!(5, fast_forward(0, rho, 1_000_000_000_000));
+    }
+}
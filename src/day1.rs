@@ -1,3 +1,6 @@
+solution!(1, 1, "The Tyranny of the Rocket Equation", "1.txt", run1);
+solution!(1, 2, "The Tyranny of the Rocket Equation", "1.txt", run2);
+
 pub fn run1(inputs: Vec<u32>) -> u32 {
     inputs.into_iter()
         .map(to_fuel)
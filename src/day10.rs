@@ -1,37 +1,43 @@
+use crate::config::Config;
+use crate::error::{Error, ParseError};
 use std::str::FromStr;
 use itertools::Itertools;
+use crate::grid::{Grid, Pos};
+use crate::math;
+use crate::parallel;
 use std::collections::HashMap;
 use std::f64::consts::PI;
 
-pub fn run1(input: Vec<MapRow>) -> usize {
-    let height = input.len();
-    let width = input[0].0.len();
-    itertools::iproduct!(0..width, 0..height)
-        .filter(|&target| get(&input, target) == Tile::Asteroid)
-        .map(|start| {
-            let c = count_asteroids(&input, width, height, start);
-            log::debug!("start: {:?} count: {}", start, c);
-            c
-        })
-        .max()
-        .unwrap()
+solution!(10, 1, "Monitoring Station", "10.txt", run1, &[]);
+solution!(10, 2, "Monitoring Station", "10.txt", run2, &[]);
+
+pub fn run1(input: Vec<MapRow>, config: &Config) -> Result<usize, Error> {
+    let map = to_grid(input)?;
+    let asteroids = asteroids(&map);
+    let counts = all_counts(&map, &asteroids, config.jobs());
+    for (start, c) in asteroids.iter().zip(&counts) {
+        log::debug!("start: {:?} count: {}", start, c);
+    }
+    Ok(counts.into_iter().max().unwrap())
 }
 
-pub fn run2(input: Vec<MapRow>) -> Point {
-    let height = input.len();
-    let width = input[0].0.len();
+pub fn run2(input: Vec<MapRow>, config: &Config) -> Result<Pos, Error> {
+    let map = to_grid(input)?;
+    let asteroids = asteroids(&map);
 
     // Find the laser (using part one's algorithm)
-    let laser: Point = itertools::iproduct!(0..width, 0..height)
-        .filter(|&target| get(&input, target) == Tile::Asteroid)
-        .max_by_key(|&start| count_asteroids(&input, width, height, start))
-        .unwrap();
+    let laser = asteroids.iter()
+        .copied()
+        .zip(all_counts(&map, &asteroids, config.jobs()))
+        .max_by_key(|&(_, c)| c)
+        .unwrap()
+        .0;
     log::info!("laser: {:?}", laser);
 
     // get groups of asteroids in a line
-    let mut groups: HashMap<(isize, isize), Vec<Point>> = HashMap::new();
-    itertools::iproduct!(0..width, 0..height)
-        .filter(|&target| get(&input, target) == Tile::Asteroid)
+    let mut groups: HashMap<(i64, i64), Vec<Pos>> = HashMap::new();
+    asteroids.iter()
+        .copied()
         .filter(|&target| laser != target)
         .for_each(|target| {
             let slope = get_slope(laser, target);
@@ -43,7 +49,7 @@ pub fn run2(input: Vec<MapRow>) -> Point {
         });
 
     // Sort keys in order of angle from start
-    let ordered: Vec<(isize, isize)> = groups.keys()
+    let ordered: Vec<(i64, i64)> = groups.keys()
         .sorted_by(|&&a, &&b| get_angle(a).partial_cmp(&get_angle(b)).unwrap())
         .copied()
         .collect();
@@ -52,13 +58,13 @@ pub fn run2(input: Vec<MapRow>) -> Point {
     let mut i = 0;
     for k in ordered.iter().cycle() {
         if groups.values().all(|it| it.is_empty()) {
-            panic!("Ran out of items")
+            return Err(Error::Unsolvable(String::from("Vaporized every asteroid before the 200th")))
         }
         if let Some(v) = groups.get_mut(k) {
             if let Some(vaporized) = v.pop() {
                 i += 1;
                 if i == 200 {
-                    return vaporized
+                    return Ok(vaporized)
                 }
             }
         }
@@ -66,13 +72,18 @@ pub fn run2(input: Vec<MapRow>) -> Point {
     unreachable!()
 }
 
+#[derive(Clone)]
 pub struct MapRow(Vec<Tile>);
 
 impl FromStr for MapRow {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(MapRow(s.chars().map(|it| Tile::from_char(it)).collect()))
+        s.chars()
+            .enumerate()
+            .map(|(i, c)| Tile::from_char(c).ok_or_else(|| ParseError::at(i + 1, format!("unknown tile '{}'", c))))
+            .collect::<Result<_, _>>()
+            .map(MapRow)
     }
 }
 
@@ -83,71 +94,60 @@ pub enum Tile {
 }
 
 impl Tile {
-    fn from_char(c: char) -> Tile {
+    fn from_char(c: char) -> Option<Tile> {
         match c {
-            '.' => Tile::Blank,
-            '#' => Tile::Asteroid,
-            _ => panic!("Unknown tile: {}", c)
+            '.' => Some(Tile::Blank),
+            '#' => Some(Tile::Asteroid),
+            _ => None
         }
     }
 }
 
-type Point = (usize, usize);
+fn to_grid(input: Vec<MapRow>) -> Result<Grid<Tile>, Error> {
+    Grid::from_rows(input.into_iter().map(|it| it.0).collect())
+}
 
-fn get(map: &Vec<MapRow>, (x, y): Point) -> Tile {
-    (&map[y].0)[x]
+// Column by column, which decides both the laser and the order asteroids on a line are vaporized in
+fn asteroids(map: &Grid<Tile>) -> Vec<Pos> {
+    let mut res: Vec<Pos> = map.iter().filter(|&(_, &tile)| tile == Tile::Asteroid).map(|(pos, _)| pos).collect();
+    res.sort();
+    res
 }
 
-fn count_asteroids(map: &Vec<MapRow>, width: usize, height: usize, start: Point) -> usize {
-    itertools::iproduct!(0..width, 0..height)
-        .filter(|&target| get(map, target) == Tile::Asteroid)
-        .filter(|&target| start != target && can_see(map, width, height, start, target))
+fn count_asteroids(map: &Grid<Tile>, asteroids: &[Pos], start: Pos) -> usize {
+    asteroids.iter()
+        .filter(|&&target| start != target && can_see(map, start, target))
         .count()
 }
 
-fn can_see(map: &Vec<MapRow>, width: usize, height: usize, start: Point, target: Point) -> bool {
-    assert_ne!(start, target);
-    let dx = target.0 as isize - start.0 as isize;
-    let dy = target.1 as isize - start.1 as isize;
-    let sx = if dx == 0 { 0 } else if dy == 0 { dx / dx.abs() } else { dx / gcd(dx.abs(), dy.abs()) };
-    let sy = if dy == 0 { 0 } else if dx == 0 { dy / dy.abs() } else { dy / gcd(dx.abs(), dy.abs()) };
+// How many each asteroid can see, in the same order
+fn all_counts(map: &Grid<Tile>, asteroids: &[Pos], jobs: usize) -> Vec<usize> {
+    parallel::map(jobs, asteroids.to_vec(), |start| count_asteroids(map, asteroids, start))
+}
 
-    let mut x = add(start.0, sx);
-    let mut y = add(start.1, sy);
+fn can_see(map: &Grid<Tile>, start: Pos, target: Pos) -> bool {
+    assert_ne!(start, target);
+    let (sx, sy) = get_slope(start, target);
+    let mut pos = (start.0 + sx, start.1 + sy);
     log::trace!("start = {:?}, target = {:?}, step = {:?}", start, target, (sx, sy));
-    while (x, y) != target && x < width && y < height {
-        if get(map, (x, y)) != Tile::Blank {
-            return false
+    while pos != target {
+        match map.get(pos) {
+            Some(Tile::Blank) => pos = (pos.0 + sx, pos.1 + sy),
+            Some(_) => return false,
+            None => break,
         }
-        x = add(x, sx);
-        y = add(y, sy);
     }
-    return true
+    true
 }
 
-fn add(a: usize, b: isize) -> usize {
-    ((a as isize) + b) as usize
+fn get_slope(start: Pos, target: Pos) -> (i64, i64) {
+    let dx = target.0 - start.0;
+    let dy = target.1 - start.1;
+    let g = math::gcd(dx, dy);
+    (dx / g, dy / g)
 }
 
-fn gcd(a: isize, b: isize) -> isize {
-    if a > b {
-        gcd(a - b, b)
-    } else if a < b {
-        gcd(a, b - a)
-    } else {
-        a
-    }
-}
-
-fn get_slope(start: Point, target: Point) -> (isize, isize) {
-    let dx = target.0 as isize - start.0 as isize;
-    let dy = target.1 as isize - start.1 as isize;
-    let sx = if dx == 0 { 0 } else if dy == 0 { dx / dx.abs() } else { dx / gcd(dx.abs(), dy.abs()) };
-    let sy = if dy == 0 { 0 } else if dx == 0 { dy / dy.abs() } else { dy / gcd(dx.abs(), dy.abs()) };
-    (sx, sy)
-}
-
-fn get_angle((dx, dy): (isize, isize)) -> f64 {
+fn get_angle((dx, dy): (i64, i64)) -> f64 {
     // laser starts pointing up and rotates clockwise
     if dx == 0 && dy < 0 {
         0.0
@@ -160,7 +160,7 @@ fn get_angle((dx, dy): (isize, isize)) -> f64 {
     }
 }
 
-fn stoa(dx: isize, dy: isize) -> f64 {
+fn stoa(dx: i64, dy: i64) -> f64 {
     (PI / 2.0) + (dy as f64 / dx as f64).atan()
 }
 
@@ -169,12 +169,6 @@ mod test {
     use super::*;
     use crate::parse_input;
 
-    #[test]
-    fn gcd_test() {
-        assert_eq!(4, gcd(12, 8));
-        assert_eq!(4, gcd(8, 12));
-    }
-
     #[test]
     fn simple() {
 
@@ -182,12 +176,13 @@ mod test {
 .....
 #####
 ....#
-...##");
-        assert_eq!(true, can_see(&map, 5, 5, (0, 2), (1, 2)));
-        assert_eq!(false, can_see(&map, 5, 5, (0, 2), (2, 2)));
-        assert_eq!(false, can_see(&map, 5, 5, (4, 4), (4, 2)));
-        assert_eq!(6, count_asteroids(&map, 5, 5, (0, 2)));
-        assert_eq!(8, run1(map));
+...##").unwrap();
+        let grid = to_grid(map.clone()).unwrap();
+        assert!(can_see(&grid, (0, 2), (1, 2)));
+        assert!(!can_see(&grid, (0, 2), (2, 2)));
+        assert!(!can_see(&grid, (4, 4), (4, 2)));
+        assert_eq!(6, count_asteroids(&grid, &asteroids(&grid), (0, 2)));
+        assert_eq!(Ok(8), run1(map, &Config::default()));
     }
 
     #[test]
@@ -201,7 +196,14 @@ mod test {
 #..#....#.
 .##.#..###
 ##...#..#.
-.#....####");
-        assert_eq!(33, run1(map));
+.#....####").unwrap();
+        assert_eq!(Ok(33), run1(map, &Config::default()));
+    }
+
+    #[test]
+    fn bad_tile() {
+        assert_eq!(Some(3), "..x#".parse::<MapRow>().err().unwrap().column);
+        let e = run1(parse_input("..#\n.#").unwrap(), &Config::default()).err().unwrap();
+        assert_eq!("<input>:2:3: expected a row 3 wide", e.to_string());
     }
 }
@@ -1,30 +1,41 @@
+use crate::error::Error;
+use crate::answer::Answer;
+use crate::config::Config;
 use crate::intcode;
-use std::collections::HashMap;
+use crate::{export, ocr, pnm, visualize};
+use crate::visualize::{Color, Picture};
+use crate::grid::{Grid, Pos};
 use crate::intcode::{Program, Computer, Stream, ComputerState};
 
-pub fn run1(input: Vec<String>) -> usize {
-    let program = intcode::parse_program(&input[0]);
-    let mut grid: HashMap<Point, u8> = HashMap::new();
-    run_robot(program, &mut grid);
-    print_grid(&grid);
-    grid.keys().count()
+solution!(11, 1, "Space Police", "11.txt", run1, &[]);
+solution!(11, 2, "Space Police", "11.txt", run2, &[]);
+
+pub fn run1(input: Vec<String>, config: &Config) -> Result<usize, Error> {
+    let program = intcode::read_program(&input)?;
+    let mut grid = Grid::sparse();
+    run_robot(program, &mut grid, config);
+    log::debug!("hull:\n{}", render_grid(&grid));
+    export::write(config, "11-1.pgm", to_pgm(&grid));
+    Ok(grid.len())
 }
 
-pub fn run2(input: Vec<String>) {
-    let program = intcode::parse_program(&input[0]);
-    let mut grid: HashMap<Point, u8> = HashMap::new();
+pub fn run2(input: Vec<String>, config: &Config) -> Result<Answer, Error> {
+    let program = intcode::read_program(&input)?;
+    let mut grid = Grid::sparse();
     grid.insert((0, 0), 1);
-    run_robot(program, &mut grid);
-    print_grid(&grid);
+    run_robot(program, &mut grid, config);
+    log::debug!("hull:\n{}", render_grid(&grid));
+    export::write(config, "11-2.pbm", to_pbm(&grid));
+    // The robot's y axis points up, images and the OCR expect it to point down
+    Ok(ocr::answer(grid.flip_y().rows(|_, it| it == Some(&1))))
 }
 
-type Point = (i32, i32);
 #[derive(Copy, Clone, Debug)]
 enum Direction {
     Up, Left, Down, Right
 }
 
-fn run_robot(program: Program, grid: &mut HashMap<Point, u8>) {
+fn run_robot(program: Program, grid: &mut Grid<u8>, config: &Config) {
     let mut pos = (0, 0);
     let mut dir = Direction::Up;
 
@@ -32,7 +43,7 @@ fn run_robot(program: Program, grid: &mut HashMap<Point, u8>) {
     comp.set_input(Some(Stream::new_wrapped()));
     comp.set_output(Some(Stream::new_wrapped()));
     loop {
-        let current = grid.get(&pos).copied().unwrap_or(0);
+        let current = grid.get(pos).copied().unwrap_or(0);
         log::debug!("current color: {}", current);
         comp.input().unwrap().borrow_mut().write(current as i64);
         let state = comp.execute();
@@ -49,6 +60,13 @@ fn run_robot(program: Program, grid: &mut HashMap<Point, u8>) {
             _ => panic!("Unknown turn direction: {}", turn),
         };
         pos = move_n(pos, dir, 1);
+        if visualize::enabled(config) {
+            visualize::show(config, &Picture::new(&render_robot(grid, pos, dir), |c| match c {
+                '#' => Color::White,
+                '.' => Color::Gray,
+                _ => Color::Yellow,
+            }));
+        }
     }
 }
 
@@ -70,7 +88,7 @@ fn turn_right(dir: Direction) -> Direction {
     }
 }
 
-fn move_n((x, y): Point, dir: Direction, n: i32) -> Point {
+fn move_n((x, y): Pos, dir: Direction, n: i64) -> Pos {
     match dir {
         Direction::Up => (x, y + n),
         Direction::Left => (x - n, y),
@@ -79,18 +97,38 @@ fn move_n((x, y): Point, dir: Direction, n: i32) -> Point {
     }
 }
 
-fn print_grid(grid: &HashMap<Point, u8>) {
-    let minx = grid.keys().map(|&(x, _)| x).min().unwrap_or(0);
-    let miny = grid.keys().map(|&(_, y)| y).min().unwrap_or(0);
-    let maxx = grid.keys().map(|&(x, _)| x).max().unwrap_or(0);
-    let maxy = grid.keys().map(|&(_, y)| y).max().unwrap_or(0);
+fn render_grid(grid: &Grid<u8>) -> String {
+    grid.flip_y().render(|it| if it == Some(&1) { '#' } else { ' ' })
+}
 
-    for y in (miny..maxy+1).rev() {
-        let mut s = String::new();
-        for x in minx..maxx+1 {
-            let color = grid.get(&(x, y)).copied().unwrap_or(0);
-            s.push(if color == 1 { '#' } else { ' ' })
+// Painted black panels are shown so the robot's trail stands out
+fn render_robot(grid: &Grid<u8>, robot: Pos, dir: Direction) -> String {
+    let mut grid = grid.clone();
+    grid.insert(robot, grid.get(robot).copied().unwrap_or(0));
+    grid.flip_y().render_at(|(x, y), it| if (x, -y) == robot {
+        match dir {
+            Direction::Up => '^',
+            Direction::Left => '<',
+            Direction::Down => 'v',
+            Direction::Right => '>',
         }
-        println!("{}", s);
-    }
+    } else {
+        match it {
+            Some(1) => '#',
+            Some(_) => '.',
+            None => ' ',
+        }
+    })
+}
+
+// Black panels are set, so the white letters come out white
+fn to_pbm(grid: &Grid<u8>) -> String {
+    let rows = grid.flip_y().rows(|_, it| it != Some(&1));
+    pnm::pbm(rows.first().map_or(0, Vec::len), rows.len(), |x, y| rows[y][x])
+}
+
+// Unpainted panels are gray
+fn to_pgm(grid: &Grid<u8>) -> String {
+    let rows = grid.flip_y().rows(|_, it| it.map_or(1, |&color| 2 * color as u16));
+    pnm::pgm(rows.first().map_or(0, Vec::len), rows.len(), 2, |x, y| rows[y][x])
 }
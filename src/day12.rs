@@ -1,81 +1,67 @@
+use crate::error::{self, Error, ParseError};
+use crate::config::Config;
+use crate::{cycle, gif};
+use crate::params::{Kind, Param};
+use crate::grid::Grid;
+use crate::math;
 use std::str::FromStr;
-use std::num::ParseIntError;
-use itertools::Itertools;
-use std::cell::RefCell;
-use std::hash::{Hash, Hasher};
-use std::collections::HashSet;
-use std::collections::hash_map::DefaultHasher;
-
-pub fn run1(input: Vec<Moon>) -> u32 {
-    let moons = wrap_moons(input);
-
-    for _ in 0..1000 {
-        step(&moons);
-    }
 
-    moons.iter()
-        .map(|moon| moon.borrow().energy())
-        .sum()
-}
+solution!(12, 1, "The N-Body Problem", "12.txt", run1, &[STEPS]);
+solution!(12, 2, "The N-Body Problem", "12.txt", run2);
 
-pub fn run2(input: Vec<Moon>) -> u64 {
-    let moons = wrap_moons(input);
-    let mut x_states = HashSet::new();
-    let mut y_states = HashSet::new();
-    let mut z_states = HashSet::new();
-    let mut steps = 0;
-    let mut x_steps = None;
-    let mut y_steps = None;
-    let mut z_steps = None;
-    loop {
-        let mut x_hasher = DefaultHasher::new();
-        let mut y_hasher = DefaultHasher::new();
-        let mut z_hasher = DefaultHasher::new();
-        moons.iter().for_each(|it| {
-            it.borrow().pos.0.hash(&mut x_hasher);
-            it.borrow().vel.0.hash(&mut x_hasher);
-            it.borrow().pos.1.hash(&mut y_hasher);
-            it.borrow().vel.1.hash(&mut y_hasher);
-            it.borrow().pos.2.hash(&mut z_hasher);
-            it.borrow().vel.2.hash(&mut z_hasher);
-        });
-        if !x_states.insert(x_hasher.finish()) && x_steps.is_none() {
-            x_steps = Some(steps)
-        }
-        if !y_states.insert(y_hasher.finish()) && y_steps.is_none() {
-            y_steps = Some(steps)
-        }
-        if !z_states.insert(z_hasher.finish()) && z_steps.is_none() {
-            z_steps = Some(steps)
-        }
-        if x_steps.is_some() && y_steps.is_some() && z_steps.is_some() {
-            break
-        }
-        if steps % 10000 == 0 {
-            log::info!("steps: {} x_steps: {:?} y_steps: {:?} z_steps: {:?}", steps, x_steps, y_steps, z_steps)
-        }
-        step(&moons);
-        steps += 1;
+const STEPS: Param = Param { name: "steps", default: "1000", kind: Kind::Count, help: "Time steps to simulate before totalling the energy" };
+
+pub fn run1(input: Vec<Moon>, config: &Config) -> Result<u32, Error> {
+    let steps = config.params.get(&STEPS)?;
+    if gif::enabled(config) {
+        gif::save(config, &orbits(&input, steps));
     }
-    log::info!("steps: {} x_steps: {:?} y_steps: {:?} z_steps: {:?}", steps, x_steps, y_steps, z_steps);
-    lcm3(x_steps.unwrap(), y_steps.unwrap(), z_steps.unwrap())
+    Ok(cycle::fast_forward(input, |moons| next(moons), steps).iter()
+        .map(|moon| moon.energy())
+        .sum())
+}
+
+// The axes don't affect each other, so each one repeats on its own and they all line up at the lcm
+pub fn run2(input: Vec<Moon>) -> i64 {
+    let periods: Vec<i64> = (0..3)
+        .map(|axis| {
+            let cycle = cycle::brent(project(&input, axis), |moons| next(moons));
+            log::info!("axis {}: {:?}", axis, cycle);
+            // Each step can be undone, so every state is on the cycle and the first one repeats
+            assert_eq!(0, cycle.tail);
+            cycle.period as i64
+        })
+        .collect();
+    math::lcm_all(periods)
 }
 
 type Vector = (i32, i32, i32);
-#[derive(Clone, Hash)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Moon {
     pos: Vector,
     vel: Vector,
 }
 
 impl FromStr for Moon {
-    type Err = ParseIntError;
+    type Err = ParseError;
 
+    // <x=-1, y=0, z=2>
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let comps: Result<Vec<i32>, _> = s[1..s.len()-1].split(", ").map(|it| it[2..it.len()].parse()).collect();
-        let comps = comps?;
-        let pos = (comps[0], comps[1], comps[2]);
-        Ok(Moon { pos, vel: (0, 0, 0) })
+        let inner = s.strip_prefix('<').ok_or_else(|| ParseError::at(1, "expected '<'"))?;
+        let inner = inner.strip_suffix('>').ok_or_else(|| ParseError::at(s.len() + 1, "expected '>'"))?;
+        let mut comps = [0; 3];
+        let mut column = 2;
+        let mut parts = inner.split(", ");
+        for (comp, name) in comps.iter_mut().zip(&["x=", "y=", "z="]) {
+            let part = parts.next().ok_or_else(|| ParseError::at(s.len(), format!("expected {}", name)))?;
+            let value = part.strip_prefix(name).ok_or_else(|| ParseError::at(column, format!("expected {}", name)))?;
+            *comp = error::parse_number(value, column + name.len())?;
+            column += part.len() + 2;
+        }
+        if parts.next().is_some() {
+            return Err(ParseError::at(column - 2, "expected '>' after z"))
+        }
+        Ok(Moon { pos: (comps[0], comps[1], comps[2]), vel: (0, 0, 0) })
     }
 }
 
@@ -87,20 +73,63 @@ impl Moon {
     }
 }
 
-fn wrap_moons(input: Vec<Moon>) -> Vec<RefCell<Moon>> {
-    input.into_iter().map(|it| RefCell::new(it)).collect()
+// Looking down the z axis, every moon leaves a dimmer trail behind it
+fn orbits(moons: &[Moon], steps: usize) -> gif::Animation {
+    let states: Vec<Vec<Moon>> = (0..steps).scan(moons.to_vec(), |state, _| {
+        let current = state.clone();
+        *state = next(state);
+        Some(current)
+    }).collect();
+    let xs = states.iter().flatten().map(|it| it.pos.0 as i64);
+    let ys = states.iter().flatten().map(|it| it.pos.1 as i64);
+    // With no steps or no moons there are no positions, so the frames just cover the origin
+    let bounds = ((xs.clone().min().unwrap_or(0), ys.clone().min().unwrap_or(0)), (xs.max().unwrap_or(0), ys.max().unwrap_or(0)));
+    let palette = vec![
+        [0, 0, 0],
+        [255, 80, 80], [80, 255, 80], [80, 160, 255], [255, 220, 80],
+        [90, 30, 30], [30, 90, 30], [30, 60, 90], [90, 80, 30],
+    ];
+    let mut animation = gif::Animation::for_grids(bounds, 2, palette, 4);
+    let mut trails = Grid::sparse();
+    for (i, state) in states.iter().enumerate() {
+        for (m, moon) in state.iter().enumerate() {
+            trails.insert((moon.pos.0 as i64, moon.pos.1 as i64), m as u8 + 5);
+        }
+        if i % 5 == 0 {
+            let current: Vec<_> = state.iter().map(|it| (it.pos.0 as i64, it.pos.1 as i64)).collect();
+            animation.push_grid(&trails, |pos, trail| match current.iter().position(|&it| it == pos) {
+                Some(m) => m as u8 + 1,
+                None => trail.copied().unwrap_or(0),
+            });
+        }
+    }
+    animation
+}
+
+// The moons with every other axis zeroed, which never pull on each other
+fn project(moons: &[Moon], axis: usize) -> Vec<Moon> {
+    let keep = |(x, y, z): Vector| match axis {
+        0 => (x, 0, 0),
+        1 => (0, y, 0),
+        _ => (0, 0, z),
+    };
+    moons.iter().map(|moon| Moon { pos: keep(moon.pos), vel: keep(moon.vel) }).collect()
+}
+
+fn next(moons: &[Moon]) -> Vec<Moon> {
+    let mut moons = moons.to_vec();
+    step(&mut moons);
+    moons
 }
 
-fn step(moons: &Vec<RefCell<Moon>>) {
-    (0..4).permutations(2)
-        .map(|it| (it[0], it[1]))
-        .filter(|&(ai, bi)| ai < bi)
-        .for_each(|(ai, bi)| {
-            let a = &moons[ai];
-            let b = &moons[bi];
-            gravitate(&mut a.borrow_mut(), &mut b.borrow_mut());
-        });
-    moons.iter().for_each(|moon| velocitate(&mut moon.borrow_mut()));
+fn step(moons: &mut [Moon]) {
+    for bi in 1..moons.len() {
+        let (head, tail) = moons.split_at_mut(bi);
+        for a in head {
+            gravitate(a, &mut tail[0]);
+        }
+    }
+    moons.iter_mut().for_each(velocitate);
 }
 
 fn gravitate(a: &mut Moon, b: &mut Moon) {
@@ -136,30 +165,16 @@ fn velocitate(moon: &mut Moon) {
     moon.pos = (moon.vel.0 + moon.pos.0, moon.vel.1 + moon.pos.1, moon.vel.2 + moon.pos.2);
 }
 
-fn gcd(a: u64, b: u64) -> u64 {
-    if a > b {
-        gcd(a - b, b)
-    } else if a < b {
-        gcd(a, b - a)
-    } else {
-        a
-    }
-}
-
-fn lcm(a: u64, b: u64) -> u64 {
-    let gcd = gcd(a, b);
-    (a / gcd) * (b / gcd)
-}
-
-fn lcm3(a: u64, b: u64, c: u64) -> u64 {
-    lcm(lcm(a, b), c)
-}
-
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::parse_input;
 
+    const EXAMPLE: &str = "<x=-1, y=0, z=2>
+<x=2, y=-10, z=-7>
+<x=4, y=-8, z=8>
+<x=3, y=5, z=-1>";
+
     #[test]
     fn energy() {
         assert_eq!(36, Moon { pos: (2, 1, -3), vel: (-3, -2, 1) }.energy());
@@ -167,13 +182,32 @@ mod test {
 
     #[test]
     fn gravitate() {
-        let input = parse_input("<x=-1, y=0, z=2>
-<x=2, y=-10, z=-7>
-<x=4, y=-8, z=8>
-<x=3, y=5, z=-1>");
-        let moons = wrap_moons(input);
-        step(&moons);
-        assert_eq!((2, -1, 1), moons[0].borrow().pos);
-        assert_eq!((3, -1, -1), moons[0].borrow().vel);
+        let mut moons: Vec<Moon> = parse_input(EXAMPLE).unwrap();
+        step(&mut moons);
+        assert_eq!((2, -1, 1), moons[0].pos);
+        assert_eq!((3, -1, -1), moons[0].vel);
+    }
+
+    #[test]
+    fn animation() {
+        let moons: Vec<Moon> = parse_input(EXAMPLE).unwrap();
+        assert_eq!(2, orbits(&moons, 10).len());
+        assert_eq!(0, orbits(&moons, 0).len());
+        assert_eq!(2, orbits(&[], 10).len());
+    }
+
+    #[test]
+    fn pt2ex1() {
+        assert_eq!(2772, run2(parse_input(EXAMPLE).unwrap()));
+    }
+
+    #[test]
+    fn bad_moons() {
+        let column = |s: &str| s.parse::<Moon>().err().unwrap().column;
+        assert_eq!(Some(10), column("<x=-1, y=, z=2>"));
+        assert_eq!(Some(8), column("<x=-1, q=0, z=2>"));
+        assert_eq!(Some(16), column("<x=-1, y=0, z=2"));
+        assert_eq!(Some(16), column("<x=-1, y=0, z=2, w=3>"));
+        assert_eq!(Some(11), column("<x=-1, y=0>"));
     }
 }
@@ -1,34 +1,40 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::{interactive, visualize};
+use crate::visualize::{Color, Frame};
 use crate::intcode::*;
-use std::collections::HashMap;
-use std::io::{stdin, stdout, Write};
+use crate::grid::Grid;
 
-pub fn run1(input: Vec<String>) -> usize {
-    let mut comp = Computer::new(parse_program(&input[0]));
+solution!(13, 1, "Care Package", "13.txt", run1);
+solution!(13, 2, "Care Package", "13.txt", run2, &[]);
+
+pub fn run1(input: Vec<String>) -> Result<usize, Error> {
+    let mut comp = Computer::new(read_program(&input)?);
     comp.set_output(Some(Stream::new_wrapped()));
     comp.execute();
     let output = comp.output().unwrap().borrow_mut().read_all();
     log::debug!("output.len: {}", output.len());
-    let mut screen = HashMap::new();
-    update_screen(&mut screen, output);
-    log::debug!("{:#?}", screen);
-    screen.values().filter(|&&it| it == 2).count()
+    let mut screen = Screen::default();
+    screen.update(output);
+    log::debug!("\n{}", screen.render());
+    Ok(screen.count(2))
 }
 
-pub fn run2(input: Vec<String>) {
-    let mut program = parse_program(&input[0]);
+// Plays itself, stepping a frame at a time when interactive. Returns the final score.
+pub fn run2(input: Vec<String>, config: &Config) -> Result<Value, Error> {
+    let mut program = read_program(&input)?;
     program[0] = 2;
     let mut comp = Computer::new(program);
     comp.set_input(Some(Stream::new_wrapped()));
     comp.set_output(Some(Stream::new_wrapped()));
-    let mut screen = HashMap::new();
-    let mut inp = String::new();
+    let mut screen = Screen::default();
     let mut last_ball_pos: Option<i64> = None;
     while ComputerState::WaitingOnInput == comp.execute() {
         let output = comp.output().unwrap().borrow_mut().read_all();
-        update_screen(&mut screen, output);
+        screen.update(output);
 
-        let ball_pos = find_ball(&screen);
-        let paddle = find_paddle(&screen);
+        let ball_pos = screen.find(4);
+        let paddle = screen.find(3);
         let i = if ball_pos == paddle {
             log::debug!("input: 0");
             0
@@ -42,55 +48,85 @@ pub fn run2(input: Vec<String>) {
         };
         last_ball_pos = Some(ball_pos);
 
-        // Comment to skip watching it play
-        print_screen(&screen);
-        inp.clear();
-        print!(">>> ");
-        stdout().flush().unwrap();
-        stdin().read_line(&mut inp).unwrap();
+        visualize::show(config, &screen);
+        if interactive::enabled(config) {
+            if !visualize::enabled(config) {
+                println!("{}", screen.render());
+            }
+            interactive::prompt(">>> ");
+        }
 
         comp.input().unwrap().borrow_mut().write(i);
     }
     let output = comp.output().unwrap().borrow_mut().read_all();
-    update_screen(&mut screen, output);
-    print_screen(&screen);
+    screen.update(output);
+    visualize::show(config, &screen);
+    log::debug!("\n{}", screen.render());
+    let blocks = screen.count(2);
+    if blocks > 0 {
+        log::warn!("Lost with {} blocks left", blocks);
+    }
+    Ok(screen.score)
 }
 
-fn update_screen(screen: &mut HashMap<(Value, Value), Value>, output: Vec<Value>) {
-    output.chunks(3)
-        .map(|it| (it[0], it[1], it[2]))
-        .for_each(|(x, y, tile)| {
-            screen.insert((x, y), tile);
-        });
+#[derive(Default)]
+struct Screen {
+    tiles: Grid<Value>,
+    score: Value,
 }
 
-fn print_screen(screen: &HashMap<(Value, Value), Value>) {
-    let score = screen.get(&(-1, 0)).copied().unwrap_or(0);
-    println!("Score: {}", score);
-    let width = screen.keys().map(|&(x, _)| x).max().unwrap_or(0) + 1;
-    let height = screen.keys().map(|&(_, y)| y).max().unwrap_or(0) + 1;
-    for y in 0..height {
-        let mut s = String::new();
-        for x in 0..width {
-            let tile = screen.get(&(x, y)).copied().unwrap_or(0);
-            let c = match tile {
-                0 => ' ',
-                1 => '█',
-                2 => '#',
-                3 => '=',
-                4 => '*',
-                _ => panic!("Unknown tile: {}", tile)
-            };
-            s.push(c)
+impl Screen {
+    fn update(&mut self, output: Vec<Value>) {
+        for it in output.chunks(3) {
+            match (it[0], it[1], it[2]) {
+                (-1, 0, score) => self.score = score,
+                (x, y, tile) => self.tiles.insert((x, y), tile),
+            }
         }
-        println!("{}", s);
+    }
+
+    fn count(&self, tile: Value) -> usize {
+        self.tiles.count(|&it| it == tile)
+    }
+
+    // The x of the first tile of the kind
+    fn find(&self, tile: Value) -> Value {
+        self.tiles.find(|&it| it == tile).map(|(x, _)| x).unwrap()
+    }
+
+    fn render(&self) -> String {
+        let tiles = self.tiles.render(|tile| tile_char(tile.copied().unwrap_or(0)));
+        format!("Score: {}\n{}", self.score, tiles)
     }
 }
 
-fn find_ball(screen: &HashMap<(Value, Value), Value>) -> i64 {
-    screen.iter().find(|&(_, &tile)| tile == 4).map(|(&(x, _), _)| x).unwrap()
+fn tile_char(tile: Value) -> char {
+    match tile {
+        0 => ' ',
+        1 => '█',
+        2 => '#',
+        3 => '=',
+        4 => '*',
+        tile => panic!("Unknown tile: {}", tile)
+    }
 }
 
-fn find_paddle(screen: &HashMap<(Value, Value), Value>) -> i64 {
-    screen.iter().find(|&(_, &tile)| tile == 3).map(|(&(x, _), _)| x).unwrap()
+impl Frame for Screen {
+    fn rows(&self) -> Vec<Vec<(char, Color)>> {
+        self.tiles.rows(|_, tile| {
+            let tile = tile.copied().unwrap_or(0);
+            let color = match tile {
+                1 => Color::Gray,
+                2 => Color::Blue,
+                3 => Color::Yellow,
+                4 => Color::Red,
+                _ => Color::Default,
+            };
+            (tile_char(tile), color)
+        })
+    }
+
+    fn caption(&self) -> String {
+        format!("Score: {}", self.score)
+    }
 }
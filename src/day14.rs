@@ -1,22 +1,43 @@
 use std::cmp::min;
 use std::collections::HashMap;
-use std::num::ParseIntError;
 use std::str::FromStr;
 use std::fmt::Debug;
+use crate::config::Config;
+use crate::error::{self, Error, ParseError};
+use crate::params::{Kind, Param};
+
+solution!(14, 1, "Space Stoichiometry", "14.txt", run1);
+solution!(14, 2, "Space Stoichiometry", "14.txt", run2, &[ORE, LOW, HIGH]);
+
+const ORE: Param = Param { name: "ore", default: "1000000000000", kind: Kind::Count, help: "Ore available for making fuel" };
+const LOW: Param = Param { name: "low", default: "2500000", kind: Kind::Count, help: "Least fuel to search from" };
+const HIGH: Param = Param { name: "high", default: "5000000", kind: Kind::Count, help: "Most fuel to search up to" };
 
 pub fn run1(input: Vec<Reaction>) -> u64 {
     let reactions_by_output = input.into_iter().map(|it| (it.output.0.clone(), it)).collect();
     calculate_ore(1, &reactions_by_output)
 }
 
-pub fn run2(input: Vec<Reaction>) -> u64 {
+pub fn run2(input: Vec<Reaction>, config: &Config) -> Result<u64, Error> {
     let reactions_by_output = input.into_iter().map(|it| (it.output.0.clone(), it)).collect();
-    binary_search(2500000, 5000000, |n| {
+    let available: u64 = config.params.get(&ORE)?;
+    let (low, high) = (config.params.get(&LOW)?, config.params.get(&HIGH)?);
+    if low > high {
+        return Err(Error::Input(format!("low ({}) is more than high ({})", low, high)))
+    }
+    let enough = |n| {
         log::info!("n = {}", n);
         let ore = calculate_ore(n, &reactions_by_output);
         log::info!("ore = {}", ore);
-        ore <= 1_000_000_000_000
-    })
+        ore <= available
+    };
+    if !enough(low) {
+        return Err(Error::Unsolvable(format!("{} ore isn't enough for {} fuel, the least to search from", available, low)))
+    }
+    if high < u64::MAX && enough(high + 1) {
+        return Err(Error::Unsolvable(format!("{} ore is enough for more than {} fuel, the most to search up to", available, high)))
+    }
+    Ok(binary_search(low, high, enough))
 }
 
 #[derive(Debug)]
@@ -26,28 +47,28 @@ pub struct Reaction {
 }
 
 impl FromStr for Reaction {
-    type Err = ParseIntError;
+    type Err = ParseError;
 
+    // 7 A, 1 B => 1 C
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.split("=>").map(|it| it.trim()).collect();
-        let inputs = parts[0].split(',')
-            .map(|it| {
-                let mut p = it.trim().split(' ');
-                let count = p.next().unwrap().trim().parse().unwrap();
-                let chem = p.next().unwrap().trim().to_string();
-                (chem, count)
-            })
-            .collect();
-        let output = {
-            let mut p = parts[1].split(' ');
-            let count = p.next().unwrap().trim().parse()?;
-            let chem = p.next().unwrap().trim().to_string();
-            (chem, count)
-        };
+        let (inputs, output) = s.split_once(" => ").ok_or_else(|| ParseError::new("expected inputs => output"))?;
+        let inputs = error::split_offsets(inputs, ", ")
+            .map(|(offset, term)| parse_term(term).map_err(|e| e.shift(offset)))
+            .collect::<Result<_, _>>()?;
+        let output = parse_term(output).map_err(|e| e.shift(s.len() - output.len()))?;
         Ok(Reaction { inputs, output })
     }
 }
 
+// 7 A
+fn parse_term(s: &str) -> Result<(String, u64), ParseError> {
+    let (count, chem) = s.split_once(' ').ok_or_else(|| ParseError::at(1, format!("expected a count and a chemical but got '{}'", s)))?;
+    if chem.is_empty() || chem.contains(' ') {
+        return Err(ParseError::at(count.len() + 2, format!("expected a single chemical but got '{}'", chem)))
+    }
+    Ok((chem.to_string(), error::parse_number(count, 1)?))
+}
+
 fn calculate_ore(fuel_needed: u64, reactions_by_output: &HashMap<String, Reaction>) -> u64 {
     let mut ore = 0;
     let mut needs = Vec::new();
@@ -106,18 +127,18 @@ fn calculate_ore(fuel_needed: u64, reactions_by_output: &HashMap<String, Reactio
     ore
 }
 
+// The largest n from min to max that passes, when min passes and everything after the first to fail
+// fails too
 fn binary_search(mut min: u64, mut max: u64, test: impl Fn(u64) -> bool) -> u64 {
-    loop {
-        let mid = (min + max) / 2;
+    while min < max {
+        let mid = min + (max - min).div_ceil(2);
         if test(mid) {
-            min = mid + 1;
+            min = mid;
         } else {
             max = mid - 1;
         }
-        if min == max {
-            return min
-        }
     }
+    min
 }
 
 #[cfg(test)]
@@ -135,7 +156,7 @@ mod test {
 177 ORE => 5 HKGWZ
 7 DCFZ, 7 PSHF => 2 XJWVT
 165 ORE => 2 GPVTF
-3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT");
+3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT").unwrap();
 
         assert_eq!(13312, run1(input));
     }
@@ -153,8 +174,25 @@ mod test {
 145 ORE => 6 MNCFX
 1 NVRVD => 8 CXFTF
 1 VJHF, 6 MNCFX => 4 RFSQX
-176 ORE => 6 VJHF");
+176 ORE => 6 VJHF").unwrap();
 
         assert_eq!(180697, run1(input));
     }
+
+    #[test]
+    fn bad_reactions() {
+        let column = |s: &str| s.parse::<Reaction>().err().unwrap().column;
+        assert_eq!(Some(6), column("7 A, x B => 1 C"));
+        assert_eq!(Some(13), column("7 A, 1 B => C"));
+        assert_eq!(Some(3), column("7 A B => 1 C"));
+        assert_eq!(None, column("7 A, 1 B"));
+    }
+
+    #[test]
+    fn search() {
+        assert_eq!(41, binary_search(0, 100, |n| n * n < 1700));
+        assert_eq!(5, binary_search(5, 5, |_| true));
+        assert_eq!(7, binary_search(3, 7, |_| true));
+        assert_eq!(3, binary_search(3, 7, |n| n == 3));
+    }
 }
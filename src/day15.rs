@@ -1,130 +1,138 @@
+use crate::config::Config;
+use crate::error::Error;
 use crate::intcode::*;
 use std::rc::Rc;
-use std::io::{stdout, stdin, Write};
-use std::collections::{HashMap, HashSet};
+use crate::grid::{Grid, Pos};
+use crate::{gif, search};
+use crate::visualize::{self, Color, Picture};
+use std::collections::{HashSet, VecDeque};
 
-pub fn run1(input: Vec<String>) -> usize {
-    let mut comp = Computer::new(parse_program(&input[0]));
-    let input = Stream::new_wrapped();
-    let output = Stream::new_wrapped();
-    comp.set_input(Some(Rc::clone(&input)));
-    comp.set_output(Some(Rc::clone(&output)));
-    let mut human_input = String::new();
-    let mut map = HashMap::new();
-    let mut robot = (0, 0);
-    let mut last_input = None;
-    let mut agent = Agent::new();
-    loop {
-        match comp.execute() {
-            ComputerState::Halted => panic!("unexpected halt"),
-            ComputerState::WaitingOnInput => {
-                if let Some(signal) = output.borrow_mut().read() {
-                    match signal {
-                        0 => {
-                            agent.inform_wall();
-                            map.insert(new_pos(robot, last_input.unwrap()), Tile::Wall);
-                        }
-                        1 => {
-                            robot = new_pos(robot, last_input.unwrap());
-                            agent.inform_move();
-                            map.insert(robot, Tile::Open);
-                        }
-                        2 => {
-                            agent.inform_move();
-                            log::info!("goal pos: {:?}", new_pos(robot, last_input.unwrap()));
-                            break agent.path_length()
-                        },
-                        _ => panic!("Unexpected output signal: {}", signal)
-                    }
-                }
-                print_screen(&map, robot);
-                prompt(&mut human_input);
-                let dir = agent.decide_move(&map, robot);
-                input.borrow_mut().write(dir.to_input());
-                last_input = Some(dir);
-            },
+solution!(15, 1, "Oxygen System", "15.txt", run1, &[]);
+solution!(15, 2, "Oxygen System", "15.txt", run2, &[]);
+
+pub fn run1(input: Vec<String>, config: &Config) -> Result<usize, Error> {
+    let program = read_program(&input)?;
+    if log::log_enabled!(log::Level::Debug) {
+        for (name, mut strategy) in strategies() {
+            match explore(program.clone(), strategy.as_mut(), config) {
+                Ok(area) => log::debug!("{}: {:?}", name, area.stats),
+                Err(e) => log::debug!("{}: {}", name, e),
+            }
         }
     }
+    let area = explore(program, &mut DepthFirst::new(), config)?;
+    let path = shortest_path(&area.map, (0, 0), area.oxygen)
+        .ok_or_else(|| Error::Unsolvable("No path to the oxygen system".to_string()))?;
+    log::debug!("map:\n{}", render(&area.map, Some((0, 0)), &path));
+    Ok(path.len())
+}
+
+// Accepts either the droid's program or a map previously exported with serialize_map, told apart by
+// the map's header
+pub fn run2(input: Vec<String>, config: &Config) -> Result<u32, Error> {
+    let map = if input.first().is_some_and(|it| it.starts_with(MAP_HEADER)) {
+        deserialize_map(&input.join("\n"))?
+    } else {
+        let area = explore(read_program(&input)?, &mut DepthFirst::new(), config)?;
+        log::debug!("map:\n{}", serialize_map(&area.map));
+        area.map
+    };
+    Ok(fill_time(&map, config))
+}
+
+struct Area {
+    map: Map,
+    oxygen: Pos,
+    stats: Stats,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+struct Stats {
+    moves: usize,
+    walls: usize,
 }
 
-pub fn run2(input: Vec<String>) -> u32 {
-    let mut comp = Computer::new(parse_program(&input[0]));
+fn explore(program: Program, strategy: &mut dyn Strategy, config: &Config) -> Result<Area, Error> {
+    let mut comp = Computer::new(program);
     let input = Stream::new_wrapped();
     let output = Stream::new_wrapped();
     comp.set_input(Some(Rc::clone(&input)));
     comp.set_output(Some(Rc::clone(&output)));
-    let mut map = HashMap::new();
+    explore_with(strategy, config, |dir| {
+        input.borrow_mut().write(dir.to_input());
+        if comp.execute() == ComputerState::Halted {
+            return Err(Error::Unsolvable("The droid halted while exploring".to_string()))
+        }
+        output.borrow_mut().read().ok_or_else(|| Error::Unsolvable("The droid didn't report a status for its move".to_string()))
+    })
+}
+
+// Fails if the strategy gives up or the area runs out before the oxygen system turns up
+fn explore_with(strategy: &mut dyn Strategy, config: &Config, mut send: impl FnMut(Direction) -> Result<Value, Error>) -> Result<Area, Error> {
+    let mut map = Grid::sparse();
+    map.insert((0, 0), Tile::Open);
     let mut robot = (0, 0);
-    let mut last_input = None;
-    let mut agent = Agent::new();
-    let mut has_moved = false;
-    let mut oxygen = (1000, 1000);
-
-    while agent.path_length() > 0 || !has_moved {
-        log::trace!("loop");
-        match comp.execute() {
-            ComputerState::Halted => panic!("unexpected halt"),
-            ComputerState::WaitingOnInput => {
-                if let Some(signal) = output.borrow_mut().read() {
-                    match signal {
-                        0 => {
-                            agent.inform_wall();
-                            map.insert(new_pos(robot, last_input.unwrap()), Tile::Wall);
-                        }
-                        1 => {
-                            robot = new_pos(robot, last_input.unwrap());
-                            agent.inform_move();
-                            map.insert(robot, Tile::Open);
-                            has_moved = true;
-                        }
-                        2 => {
-                            robot = new_pos(robot, last_input.unwrap());
-                            agent.inform_move();
-                            map.insert(robot, Tile::Oxygen);
-                            has_moved = true;
-                            oxygen = robot;
-                        },
-                        _ => panic!("Unexpected output signal: {}", signal)
-                    }
-                }
-                let dir = agent.decide_move(&map, robot);
-                input.borrow_mut().write(dir.to_input());
-                last_input = Some(dir);
+    let mut oxygen = None;
+    let mut stats = Stats::default();
+    while !is_fully_mapped(&map) {
+        let dir = match strategy.decide_move(&map, robot) {
+            Some(dir) => dir,
+            None => break
+        };
+        let target = new_pos(robot, dir);
+        stats.moves += 1;
+        match send(dir)? {
+            0 => {
+                stats.walls += 1;
+                strategy.inform_wall();
+                map.insert(target, Tile::Wall);
+            }
+            1 => {
+                robot = target;
+                strategy.inform_move();
+                map.insert(robot, Tile::Open);
+            }
+            2 => {
+                robot = target;
+                strategy.inform_move();
+                map.insert(robot, Tile::Oxygen);
+                log::info!("goal pos: {:?}", robot);
+                oxygen = Some(robot);
             },
+            signal => return Err(Error::Unsolvable(format!("Unexpected output signal: {}", signal)))
+        }
+        log::trace!("map:\n{}", render(&map, Some(robot), &[]));
+        if visualize::enabled(config) {
+            let caption = format!("Moves: {} Walls: {}", stats.moves, stats.walls);
+            visualize::show(config, &Picture::new(&render(&map, Some(robot), &[]), color).with_caption(caption));
         }
     }
-    
-    let mut tips = HashSet::new();
-    tips.insert(oxygen);
-    let mut t = 0;
-    while map.values().any(|&it| it == Tile::Open) {
-        tips = spread_oxygen(tips, &mut map);
-        t += 1;
-    }
-    t
+    let oxygen = oxygen.ok_or_else(|| Error::Unsolvable(format!("Stopped exploring after {} moves without finding the oxygen system", stats.moves)))?;
+    Ok(Area { map, oxygen, stats })
 }
 
-fn prompt(inp: &mut String) {
-    inp.clear();
-    print!(">>> ");
-    stdout().flush().unwrap();
-    stdin().read_line(inp).unwrap();
+fn is_fully_mapped(map: &Map) -> bool {
+    map.iter()
+        .filter(|&(_, &tile)| tile != Tile::Wall)
+        .all(|(pos, _)| DIRECTIONS.iter().all(|&dir| map.contains(new_pos(pos, dir))))
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 enum Tile {
     Wall,
     Open,
     Oxygen,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 enum Direction {
     North, South, West, East
 }
 
+const DIRECTIONS: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+
 impl Direction {
-    fn to_input(&self) -> Value {
+    fn to_input(self) -> Value {
         match self {
             Direction::North => 1,
             Direction::South => 2,
@@ -133,7 +141,20 @@ impl Direction {
         }
     }
 
-    fn opposite(&self) -> Direction {
+    fn turn_left(self) -> Direction {
+        match self {
+            Direction::North => Direction::West,
+            Direction::West => Direction::South,
+            Direction::South => Direction::East,
+            Direction::East => Direction::North,
+        }
+    }
+
+    fn turn_right(self) -> Direction {
+        self.turn_left().opposite()
+    }
+
+    fn opposite(self) -> Direction {
         match self {
             Direction::North => Direction::South,
             Direction::South => Direction::North,
@@ -143,42 +164,85 @@ impl Direction {
     }
 }
 
-type Map = HashMap<(Value, Value), Tile>;
+type Map = Grid<Tile>;
 
-fn print_screen(screen: &Map, robot: (Value, Value)) {
-    let width_range = {
-        let xs = screen.keys().map(|&(x, _)| x);
-        let min = xs.clone().min().unwrap_or(0);
-        let max = xs.max().unwrap_or(0) + 1;
-        min..max
-    };
-    let height_range = {
-        let ys = screen.keys().map(|&(_, y)| y);
-        let min = ys.clone().min().unwrap_or(0);
-        let max = ys.max().unwrap_or(0) + 1;
-        min..max
-    };
-    for y in height_range.rev() {
-        let mut s = String::new();
-        for x in width_range.clone() {
-            let c = if (x, y) == robot {
+// North is up, and every tile is two characters wide so the map isn't squashed
+fn render(map: &Map, robot: Option<Pos>, path: &[Pos]) -> String {
+    let path: HashSet<_> = path.iter().copied().collect();
+    map.flip_y()
+        .render_at(|(x, y), tile| {
+            if Some((x, -y)) == robot {
                 'D'
             } else {
-                match screen.get(&(x, y)).copied() {
+                match tile.copied() {
                     None => ' ',
+                    Some(Tile::Oxygen) => 'O',
+                    Some(Tile::Open) if path.contains(&(x, -y)) => '*',
                     Some(Tile::Open) => '.',
                     Some(Tile::Wall) => '█',
-                    Some(Tile::Oxygen) => 'O'
                 }
-            };
-            s.push(c);
-            s.push(c);
-        }
-        println!("{}", s);
+            }
+        })
+        .chars()
+        .flat_map(|c| if c == '\n' { vec![c] } else { vec![c, c] })
+        .collect()
+}
+
+fn color(c: char) -> Color {
+    match c {
+        'D' => Color::Yellow,
+        'O' => Color::Cyan,
+        '*' => Color::Green,
+        '█' => Color::Gray,
+        _ => Color::Default,
+    }
+}
+
+// The first line is "map x,y", the position of the top left cell, followed by one row per line from
+// north to south
+const MAP_HEADER: &str = "map ";
+
+fn serialize_map(map: &Map) -> String {
+    let (min_x, max_y) = map.bounds().map_or((0, 0), |((min_x, _), (_, max_y))| (min_x, max_y));
+    let rows = map.flip_y().render(|tile| match tile {
+        None => ' ',
+        Some(Tile::Open) => '.',
+        Some(Tile::Wall) => '#',
+        Some(Tile::Oxygen) => 'O',
+    });
+    let mut s = format!("{}{},{}\n", MAP_HEADER, min_x, max_y);
+    for row in rows.lines() {
+        s.push_str(row.trim_end());
+        s.push('\n');
+    }
+    s
+}
+
+fn deserialize_map(s: &str) -> Result<Map, Error> {
+    let (header, rows) = s.split_once('\n').unwrap_or((s, ""));
+    let header_error = |reason: String| Error::Parse { file: None, line: 1, column: None, text: header.to_string(), reason };
+    let origin: Vec<Value> = header.strip_prefix(MAP_HEADER)
+        .ok_or_else(|| header_error(format!("expected a header like '{}0,0'", MAP_HEADER)))?
+        .split(',')
+        .map(|it| it.trim().parse().map_err(|e| header_error(format!("bad map header: {}", e))))
+        .collect::<Result<_, _>>()?;
+    if origin.len() != 2 {
+        return Err(header_error("bad map header: expected x,y".to_string()))
     }
+    let map = Grid::parse_sparse(rows, ' ', |c| match c {
+        '.' => Some(Tile::Open),
+        '#' => Some(Tile::Wall),
+        'O' => Some(Tile::Oxygen),
+        _ => None
+    }).map_err(|e| match e {
+        // The rows start on the second line
+        Error::Parse { file, line, column, text, reason } => Error::Parse { file, line: line + 1, column, text, reason },
+        e => e,
+    })?;
+    Ok(map.transform(|(dx, dy)| (origin[0] + dx, origin[1] - dy)))
 }
 
-fn new_pos(pos: (Value, Value), dir: Direction) -> (Value, Value) {
+fn new_pos(pos: Pos, dir: Direction) -> Pos {
     match dir {
         Direction::North => (pos.0, pos.1 + 1),
         Direction::South => (pos.0, pos.1 - 1),
@@ -187,33 +251,54 @@ fn new_pos(pos: (Value, Value), dir: Direction) -> (Value, Value) {
     }
 }
 
-struct Agent {
+trait Strategy {
+    // Returns None when the strategy has nothing left to explore
+    fn decide_move(&mut self, map: &Map, pos: Pos) -> Option<Direction>;
+    fn inform_wall(&mut self);
+    fn inform_move(&mut self);
+}
+
+fn strategies() -> Vec<(&'static str, Box<dyn Strategy>)> {
+    vec![
+        ("depth first", Box::new(DepthFirst::new())),
+        ("frontier", Box::new(Frontier::new())),
+        ("wall follower", Box::new(WallFollower::new())),
+    ]
+}
+
+struct DepthFirst {
     stack: Vec<Direction>,
     pending: Option<Direction>,
 }
 
-impl Agent {
-    fn new() -> Agent {
-        Agent {
+impl DepthFirst {
+    fn new() -> DepthFirst {
+        DepthFirst {
             stack: Vec::new(),
             pending: None,
         }
     }
+}
 
-    fn decide_move(&mut self, map: &Map, pos: (Value, Value)) -> Direction {
-        let dir = if map.get(&new_pos(pos, Direction::North)).is_none() {
+impl Strategy for DepthFirst {
+    fn decide_move(&mut self, map: &Map, pos: Pos) -> Option<Direction> {
+        let dir = if map.get(new_pos(pos, Direction::North)).is_none() {
             Direction::North
-        } else if map.get(&new_pos(pos, Direction::West)).is_none() {
+        } else if map.get(new_pos(pos, Direction::West)).is_none() {
             Direction::West
-        } else if map.get(&new_pos(pos, Direction::South)).is_none() {
+        } else if map.get(new_pos(pos, Direction::South)).is_none() {
             Direction::South
-        } else if map.get(&new_pos(pos, Direction::East)).is_none() {
+        } else if map.get(new_pos(pos, Direction::East)).is_none() {
             Direction::East
         } else {
-            return self.stack.pop().unwrap().opposite()
+            return self.stack.pop().map(Direction::opposite)
         };
         self.pending = Some(dir);
-        dir
+        Some(dir)
+    }
+
+    fn inform_wall(&mut self) {
+        self.pending = None;
     }
 
     fn inform_move(&mut self) {
@@ -221,28 +306,235 @@ impl Agent {
             self.stack.push(dir)
         }
     }
+}
+
+// Travels along the shortest known route to the nearest unknown cell
+struct Frontier {
+    route: VecDeque<Direction>,
+}
+
+impl Frontier {
+    fn new() -> Frontier {
+        Frontier {
+            route: VecDeque::new(),
+        }
+    }
+}
+
+impl Strategy for Frontier {
+    fn decide_move(&mut self, map: &Map, pos: Pos) -> Option<Direction> {
+        if self.route.is_empty() {
+            self.route = route_to_unknown(map, pos)?;
+        }
+        self.route.front().copied()
+    }
+
+    fn inform_wall(&mut self) {
+        // Every step but the last goes through known open cells, so the unknown cell was the wall
+        self.route.clear();
+    }
+
+    fn inform_move(&mut self) {
+        self.route.pop_front();
+    }
+}
+
+fn route_to_unknown(map: &Map, start: Pos) -> Option<VecDeque<Direction>> {
+    // Unknown cells are never expanded since the search stops at the first one
+    let search = search::bfs_until(
+        vec![start],
+        |&pos| DIRECTIONS.iter().map(move |&dir| new_pos(pos, dir)).filter(|&next| map.get(next) != Some(&Tile::Wall)),
+        |&pos| !map.contains(pos),
+    );
+    let path = search.goal_path()?;
+    Some(path.windows(2).map(|step| direction(step[0], step[1])).collect())
+}
+
+fn direction(from: Pos, to: Pos) -> Direction {
+    DIRECTIONS.iter().copied().find(|&dir| new_pos(from, dir) == to).expect("Expected adjacent positions")
+}
+
+// Keeps its left hand on the wall. Only maps everything when the open area has no loops.
+struct WallFollower {
+    facing: Direction,
+    pending: Option<Direction>,
+    known: usize,
+    seen: HashSet<(Pos, Direction)>,
+}
+
+impl WallFollower {
+    fn new() -> WallFollower {
+        WallFollower {
+            facing: Direction::North,
+            pending: None,
+            known: 0,
+            seen: HashSet::new(),
+        }
+    }
+}
+
+impl Strategy for WallFollower {
+    fn decide_move(&mut self, map: &Map, pos: Pos) -> Option<Direction> {
+        // Returning to the same place facing the same way without learning anything means we're going in circles
+        if map.len() != self.known {
+            self.known = map.len();
+            self.seen.clear();
+        }
+        if !self.seen.insert((pos, self.facing)) {
+            return None
+        }
+        let left = self.facing.turn_left();
+        let dir = [left, self.facing, self.facing.turn_right(), self.facing.opposite()].iter()
+            .copied()
+            .find(|&dir| map.get(new_pos(pos, dir)) != Some(&Tile::Wall))?;
+        self.pending = Some(dir);
+        Some(dir)
+    }
 
     fn inform_wall(&mut self) {
         self.pending = None;
     }
 
-    fn path_length(&self) -> usize {
-        self.stack.len()
+    fn inform_move(&mut self) {
+        if let Some(dir) = self.pending.take() {
+            self.facing = dir;
+        }
     }
 }
 
-fn spread_oxygen(tips: HashSet<(Value, Value)>, map: &mut Map) -> HashSet<(Value, Value)> {
-    let mut new_tips = HashSet::new();
-    for &t in &tips {
-        for &dir in [Direction::North, Direction::East, Direction::South, Direction::West].iter() {
-            let new_pos = new_pos(t, dir);
-            if let Some(Tile::Open) = map.get(&new_pos) {
-                new_tips.insert(new_pos);
-            }
+// A* over the discovered open tiles. The returned path excludes the start.
+fn shortest_path(map: &Map, start: Pos, goal: Pos) -> Option<Vec<Pos>> {
+    let search = search::astar(
+        start,
+        |&pos| DIRECTIONS.iter()
+            .map(move |&dir| (new_pos(pos, dir), 1))
+            .filter(|&(next, _)| matches!(map.get(next), Some(Tile::Open) | Some(Tile::Oxygen))),
+        |&(x, y)| ((goal.0 - x).abs() + (goal.1 - y).abs()) as u32,
+        |&pos| pos == goal,
+    );
+    search.goal_path().map(|path| path[1..].to_vec())
+}
+
+// The oxygen spreads to every open tile a step further each minute
+fn fill_time(map: &Map, config: &Config) -> u32 {
+    let oxygen = map.iter()
+        .filter(|&(_, &tile)| tile == Tile::Oxygen)
+        .map(|(pos, _)| pos);
+    let search = search::bfs_multi(oxygen, |&pos| DIRECTIONS.iter()
+        .map(move |&dir| new_pos(pos, dir))
+        .filter(|&next| map.get(next) == Some(&Tile::Open)));
+    let minutes = search.max_cost().unwrap_or(0);
+    if gif::enabled(config) {
+        gif::save(config, &spread_animation(map, &search, minutes));
+    }
+    minutes
+}
+
+// A frame a minute, north up
+fn spread_animation(map: &Map, search: &search::Search<Pos>, minutes: u32) -> gif::Animation {
+    let map = map.flip_y();
+    let palette = vec![[0, 0, 0], [90, 90, 90], [40, 40, 60], [80, 200, 255]];
+    let mut animation = gif::Animation::for_grids(map.bounds().unwrap(), 6, palette, 5);
+    for minute in 0..=minutes {
+        animation.push_grid(&map, |(x, y), tile| match tile {
+            None => 0,
+            Some(Tile::Wall) => 1,
+            Some(_) if search.cost(&(x, -y)).is_some_and(|it| it <= minute) => 3,
+            Some(_) => 2,
+        });
+    }
+    animation
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE: &str = "map 0,0
+ ##
+#..##
+#.#..#
+#.O.#
+ ###";
+
+    // The example with the droid starting next to the top left wall
+    const MAZE: &str = "map -1,1
+ ##
+#..##
+#.#..#
+#.O.#
+ ###";
+
+    fn simulate(maze: &Map) -> impl FnMut(Direction) -> Result<Value, Error> + '_ {
+        let mut pos = (0, 0);
+        move |dir| {
+            let target = new_pos(pos, dir);
+            Ok(match maze.get(target) {
+                Some(Tile::Wall) | None => 0,
+                Some(Tile::Open) => {
+                    pos = target;
+                    1
+                }
+                Some(Tile::Oxygen) => {
+                    pos = target;
+                    2
+                }
+            })
+        }
+    }
+
+    #[test]
+    fn strategies_map_everything() {
+        let maze = deserialize_map(MAZE).unwrap();
+        for (name, mut strategy) in strategies() {
+            let area = explore_with(strategy.as_mut(), &Config::default(), simulate(&maze)).unwrap();
+            assert_eq!(maze, area.map, "{}", name);
+            assert_eq!((1, -2), area.oxygen, "{}", name);
+            // Strategies never walk into a wall they already know about
+            assert_eq!(maze.values().filter(|&&it| it == Tile::Wall).count(), area.stats.walls, "{}", name);
+        }
+    }
+
+    #[test]
+    fn no_oxygen() {
+        let maze = deserialize_map("map -1,1\n#####\n#...#\n#.#.#\n#...#\n#####").unwrap();
+        for (name, mut strategy) in strategies() {
+            let e = explore_with(strategy.as_mut(), &Config::default(), simulate(&maze)).err();
+            assert!(matches!(e, Some(Error::Unsolvable(_))), "{}", name);
         }
+        let halts = parse_program("3,0,99").unwrap();
+        assert_eq!(Some(Error::Unsolvable("The droid halted while exploring".to_string())), explore(halts, &mut DepthFirst::new(), &Config::default()).err());
     }
-    for &t in &new_tips {
-        map.insert(t, Tile::Oxygen);
+
+    #[test]
+    fn serialize_roundtrip() {
+        let map = deserialize_map(EXAMPLE).unwrap();
+        assert_eq!(Some(&Tile::Oxygen), map.get((2, -3)));
+        assert_eq!(Some(&Tile::Wall), map.get((5, -2)));
+        assert_eq!(None, map.get((0, 0)));
+        let serialized = serialize_map(&map);
+        assert_eq!(map, deserialize_map(&serialized).unwrap());
+    }
+
+    #[test]
+    fn path() {
+        let map = deserialize_map(EXAMPLE).unwrap();
+        assert_eq!(Some(vec![(1, -2), (1, -3), (2, -3)]), shortest_path(&map, (1, -1), (2, -3)));
+        assert_eq!(None, shortest_path(&map, (1, -1), (0, 0)));
+    }
+
+    #[test]
+    fn bad_map() {
+        let e = deserialize_map("map 0,0\n#.x").err().unwrap();
+        assert_eq!("<input>:2:3: unknown tile 'x'\n  #.x\n    ^", e.to_string());
+        let e = deserialize_map("map 0\n#").err().unwrap();
+        assert_eq!("<input>:1: bad map header: expected x,y\n  map 0", e.to_string());
+    }
+
+    #[test]
+    fn pt2ex1() {
+        assert_eq!(Ok(4), run2(EXAMPLE.lines().map(String::from).collect(), &Config::default()));
+        // A single row is still a map
+        assert_eq!(Ok(0), run2(vec!["map 0,0".to_string(), "#O#".to_string()], &Config::default()));
     }
-    new_tips
 }
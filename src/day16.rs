@@ -1,24 +1,39 @@
 use itertools::Itertools;
+use crate::config::Config;
+use crate::error::{self, Error};
+use crate::params::{Kind, Param};
 
-pub fn run1(input: Vec<String>) -> String {
+solution!(16, 1, "Flawed Frequency Transmission", "16.txt", run1, &[PHASES]);
+solution!(16, 2, "Flawed Frequency Transmission", "16.txt", run2, &[PHASES, REPEAT]);
+
+const PHASES: Param = Param { name: "phases", default: "100", kind: Kind::Count, help: "Phases of FFT to run" };
+const REPEAT: Param = Param { name: "repeat", default: "10000", kind: Kind::Positive, help: "Times the input signal is repeated" };
+
+pub fn run1(input: Vec<String>, config: &Config) -> Result<String, Error> {
     let mut seq = parse(&input[0]);
-    for _ in 0..100 {
+    for _ in 0..config.params.get(&PHASES)? {
         seq = compute_phase(seq);
     }
-    seq[0..8].iter().join("")
+    Ok(seq[0..8].iter().join(""))
 }
 
-pub fn run2(input: Vec<String>) -> String {
-    let offset: usize = (&input[0]).chars().take(7).collect::<String>().parse().unwrap();
+// Past the halfway point every digit is just the sum of the digits after it, so only the tail from
+// the message offset needs computing
+pub fn run2(input: Vec<String>, config: &Config) -> Result<String, Error> {
+    let offset: usize = error::parse_number(&input[0].chars().take(7).collect::<String>(), 1).map_err(|e| e.on_line(1, &input[0]))?;
     let orig = parse(&input[0]);
+    let len = orig.len().saturating_mul(config.params.get(&REPEAT)?);
+    if offset + 8 > len || offset < len / 2 {
+        return Err(Error::Unsolvable(format!("The message offset {} has to be in the second half of the {} digit signal", offset, len)))
+    }
     let mut seq: Vec<_> = orig.iter()
         .copied()
         .rev()
         .cycle()
-        .take(orig.len() * 10000 - offset)
+        .take(len - offset)
         .collect();
 
-    for i in 0..100 {
+    for i in 0..config.params.get(&PHASES)? {
         log::info!("iteration: {}", i + 1);
         seq = seq.iter()
             .scan(0, |a, &b| {
@@ -27,7 +42,7 @@ pub fn run2(input: Vec<String>) -> String {
             })
             .collect();
     }
-    seq.iter().rev().take(8).join("")
+    Ok(seq.iter().rev().take(8).join(""))
 }
 
 fn parse(s: &str) -> Vec<i8> {
@@ -80,7 +95,17 @@ mod test {
 
     #[test]
     fn pt1ex1() {
-        let input = parse_input("80871224585914546619083218645595");
-        assert_eq!("24176176", run1(input));
+        let input = parse_input("80871224585914546619083218645595").unwrap();
+        assert_eq!(Ok(String::from("24176176")), run1(input, &Config::default()));
+    }
+
+    #[test]
+    fn pt2ex1() {
+        let input = parse_input("03036732577212944063491565474664").unwrap();
+        assert_eq!(Ok(String::from("84462026")), run2(input, &Config::default()));
+        let input = parse_input("00000012345678123456781234567812").unwrap();
+        assert!(matches!(run2(input, &Config::default()), Err(Error::Unsolvable(_))));
+        let input = parse_input("03x36732577212944063491565474664").unwrap();
+        assert!(matches!(run2(input, &Config::default()), Err(Error::Parse { line: 1, column: Some(1), .. })));
     }
 }
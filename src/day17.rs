@@ -1,30 +1,35 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::{interactive, visualize};
+use crate::visualize::{Color, Picture};
 use crate::intcode::*;
 use std::rc::Rc;
-use std::collections::HashMap;
-use itertools::Itertools;
+use crate::grid::Grid;
 
-pub fn run1(input: Vec<String>) -> usize {
-    let mut comp = Computer::new(parse_program(&input[0]));
+solution!(17, 1, "Set and Forget", "17.txt", run1);
+solution!(17, 2, "Set and Forget", "17.txt", run2, &[]);
+
+pub fn run1(input: Vec<String>) -> Result<i64, Error> {
+    let mut comp = Computer::new(read_program(&input)?);
     let output = Stream::new_wrapped();
     comp.set_output(Some(Rc::clone(&output)));
     comp.execute();
     let view: String = output.borrow_mut().read_all().iter().map(|&a| char::from(a as u8)).collect();
-    let map = to_map(&view);
-    let width = map.keys().map(|&(x, _)| x).max().unwrap() + 1;
-    let height = map.keys().map(|&(_, y)| y).max().unwrap() + 1;
+    let map = Grid::parse(view.trim_end(), Some).unwrap();
+    let intersections = map.iter()
+        .filter(|&(pos, &c)| c != '.' && map.neighbors4(pos).filter(|&(_, &it)| it != '.').count() == 4)
+        .map(|(pos, _)| pos);
     let mut sum = 0;
-    for (x, y) in (1..width-1).cartesian_product(1..height-1) {
-        if map[&(x, y)] != '.' && map[&(x-1, y)] != '.' && map[&(x+1, y)] != '.' && map[&(x, y-1)] != '.' && map[&(x, y+1)] != '.' {
-            log::debug!("intersection at ({}, {})", x, y);
-            sum += x * y
-        }
+    for (x, y) in intersections {
+        log::debug!("intersection at ({}, {})", x, y);
+        sum += x * y
     }
-    println!("{}", view);
-    sum
+    log::debug!("\n{}", view);
+    Ok(sum)
 }
 
-pub fn run2(input: Vec<String>) -> i64 {
-    let mut program = parse_program(&input[0]);
+pub fn run2(input: Vec<String>, config: &Config) -> Result<i64, Error> {
+    let mut program = read_program(&input)?;
     program[0] = 2;
     let mut comp = Computer::new(program);
     let input = Stream::new_wrapped();
@@ -32,30 +37,34 @@ pub fn run2(input: Vec<String>) -> i64 {
     comp.set_input(Some(Rc::clone(&input)));
     comp.set_output(Some(Rc::clone(&output)));
 
-    let interactive = true;
+    // Only ask for the video feed when someone is watching
+    let watching = interactive::enabled(config) || visualize::enabled(config);
     let commands = "A,B,A,B,C,A,C,A,C,B
 R,12,L,8,L,4,L,4
 L,8,R,6,L,6
 L,8,L,4,R,12,L,6,L,4
 ";
     input.borrow_mut().write_all(&to_input(commands));
-    input.borrow_mut().write_all(&to_input(if interactive { "y\n" } else { "n\n" }));
+    input.borrow_mut().write_all(&to_input(if watching { "y\n" } else { "n\n" }));
     comp.execute();
 
     let raw_output = output.borrow_mut().read_all();
     let view: String = raw_output[0..raw_output.len()-1].iter().map(|&a| char::from(a as u8)).collect();
-    println!("{}", view); // TODO show this in a more usable (interactive?) way
-    raw_output[raw_output.len()-1]
-}
-
-fn to_map(view: &str) -> HashMap<(usize, usize), char> {
-    let mut map = HashMap::new();
-    for (y, line) in view.lines().enumerate() {
-        for (x, c) in line.chars().enumerate() {
-            map.insert((x, y), c);
+    if visualize::enabled(config) {
+        // The feed is a camera image after every move, separated by blank lines
+        for frame in view.split("\n\n").filter(|it| it.contains('#')) {
+            visualize::show(config, &Picture::new(frame, |c| match c {
+                '#' => Color::Gray,
+                '^' | 'v' | '<' | '>' => Color::Yellow,
+                _ => Color::Default,
+            }));
         }
+    } else if watching {
+        println!("{}", view);
+    } else {
+        log::debug!("\n{}", view);
     }
-    map
+    Ok(raw_output[raw_output.len()-1])
 }
 
 fn to_input(s: &str) -> Vec<i64> {
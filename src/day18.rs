@@ -1,10 +1,20 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use crate::config::Config;
+use crate::error::Error;
+use crate::grid::{Grid, Pos};
+use crate::{gif, search};
+use std::collections::{HashMap, HashSet};
 use itertools::Itertools;
 use std::cmp::min;
 
-pub fn run1(input: Vec<String>) -> u32 {
-    let map = parse_map(input);
+solution!(18, 1, "Many-Worlds Interpretation", "18.txt", run1, &[]);
+solution!(18, 2, "Many-Worlds Interpretation", "18-2.txt", run2);
+
+pub fn run1(input: Vec<String>, config: &Config) -> Result<u32, Error> {
+    let map = parse_map(input, 1)?;
     let keys = map.keys();
+    if keys.is_empty() {
+        return Ok(0)
+    }
     let mut cache = HashMap::new();
     let mut nodes: HashMap<char, HashMap<String, u32>> = HashMap::new();
     keys.iter()
@@ -15,14 +25,19 @@ pub fn run1(input: Vec<String>) -> u32 {
             v.insert(String::new(), cost);
             nodes.insert(k, v);
         });
+    // Every round is only kept for working out the order to draw
+    let mut history = Vec::new();
+    if gif::enabled(config) {
+        history.push(nodes.clone());
+    }
     for _ in 0..keys.len()-1 {
-        log::info!("{:#?}", nodes);
+        log::debug!("{:#?}", nodes);
         let mut new_nodes = HashMap::new();
         for &key in &keys {
             let mut new_paths = HashMap::new();
             for (&end_key, paths) in &nodes {
                 for (via, &cost) in paths {
-                    let mut subset = str_to_set(&via);
+                    let mut subset = str_to_set(via);
                     subset.insert(end_key);
                     if !subset.contains(&key) {
                         if let Some(segment_cost) = cached_pathfind(&map, &mut cache, find_key(&map, end_key), find_key(&map, key), &subset) {
@@ -41,15 +56,84 @@ pub fn run1(input: Vec<String>) -> u32 {
             }
         }
         nodes = new_nodes;
+        if gif::enabled(config) {
+            history.push(nodes.clone());
+        }
+    }
+
+    log::debug!("{:#?}", nodes);
+
+    if gif::enabled(config) {
+        let order = collection_order(&map, &mut cache, &history);
+        log::info!("order: {}", order.iter().collect::<String>());
+        gif::save(config, &collection_animation(&map, &order));
     }
 
-    log::info!("{:#?}", nodes);
+    nodes.values().flat_map(|it| it.values()).min().copied().ok_or_else(no_route)
+}
+
+fn no_route() -> Error {
+    Error::Unsolvable("No route picks up every key".to_string())
+}
+
+type Nodes = HashMap<char, HashMap<String, u32>>;
+
+// Works back through the rounds for the key before each one whose cost adds up
+fn collection_order(map: &Map, cache: &mut Cache, history: &[Nodes]) -> Vec<char> {
+    let (mut key, mut via, mut cost) = history.last().unwrap().iter()
+        .flat_map(|(&k, paths)| paths.iter().map(move |(via, &cost)| (k, via.clone(), cost)))
+        .min_by_key(|it| it.2)
+        .unwrap();
+    let mut order = vec![key];
+    for nodes in history[..history.len() - 1].iter().rev() {
+        let held = str_to_set(&via);
+        let prev = held.iter().copied().find_map(|prev| {
+            let mut before = held.clone();
+            before.remove(&prev);
+            let before = set_to_string(&before);
+            let prev_cost = *nodes.get(&prev)?.get(&before)?;
+            let segment = cached_pathfind(map, cache, find_key(map, prev), find_key(map, key), &held)?;
+            if prev_cost + segment == cost { Some((prev, before, prev_cost)) } else { None }
+        });
+        let (prev, before, prev_cost) = prev.expect("Expected a previous key");
+        order.push(prev);
+        key = prev;
+        via = before;
+        cost = prev_cost;
+    }
+    order.reverse();
+    order
+}
 
-    nodes.values().flat_map(|it| it.values()).min().copied().unwrap()
+// A frame per step, leaving a trail and opening doors as their keys are picked up
+fn collection_animation(map: &Map, order: &[char]) -> gif::Animation {
+    let palette = vec![[60, 60, 60], [10, 10, 10], [40, 70, 120], [255, 210, 0], [200, 60, 40], [80, 255, 120]];
+    let mut animation = gif::Animation::for_grids(map.map.bounds().unwrap(), 3, palette, 2);
+    let mut held = HashSet::new();
+    let mut trail = HashSet::new();
+    let mut robot = map.starts[0];
+    for &key in order {
+        let goal = find_key(map, key);
+        let search = search::bfs_until(vec![robot], |&pos| neighbors(map, pos, &held), |&pos| pos == goal);
+        for step in search.goal_path().expect("Expected a path to the next key").into_iter().skip(1) {
+            robot = step;
+            trail.insert(step);
+            animation.push_grid(&map.map, |pos, tile| match tile {
+                _ if pos == robot => 5,
+                Some(Tile::Wall) => 0,
+                Some(Tile::Key(k)) if !held.contains(k) => 3,
+                Some(Tile::Door(d)) if !held.contains(&d.to_ascii_lowercase()) => 4,
+                _ if trail.contains(&pos) => 2,
+                _ => 1,
+            });
+        }
+        held.insert(key);
+    }
+    animation
 }
 
-pub fn run2(input: Vec<String>) -> u32 {
-    let map = parse_map(input);
+pub fn run2(input: Vec<String>) -> Result<u32, Error> {
+    let map = parse_map(input, 4)?;
     let keys = map.keys();
     let mut cache = HashMap::new();
     let pkeys = {
@@ -79,14 +163,14 @@ pub fn run2(input: Vec<String>) -> u32 {
     }
 
     for _ in 0..keys.len() {
-        log::info!("{:#?}", nodes);
+        log::debug!("{:#?}", nodes);
         let mut new_nodes: HashMap<[char;4], HashMap<String, u32>> = HashMap::new();
         for &key in &keys {
             let p = pkeys.iter().enumerate().find(|it| it.1.contains(&key)).expect("key not found in partitions").0;
             for (&end_keys, paths) in &nodes {
                 let mut new_paths = HashMap::new();
                 for (via, &cost) in paths {
-                    let mut held_keys = str_to_set(&via);
+                    let mut held_keys = str_to_set(via);
                     for &end_key in &end_keys {
                         held_keys.insert(end_key);
                     }
@@ -124,22 +208,22 @@ pub fn run2(input: Vec<String>) -> u32 {
         nodes = new_nodes;
     }
 
-    log::info!("{:#?}", nodes);
+    log::debug!("{:#?}", nodes);
 
-    nodes.values().flat_map(|it| it.values()).min().copied().unwrap()
+    nodes.values().flat_map(|it| it.values()).min().copied().ok_or_else(no_route)
 }
 
 type Cache = HashMap<CacheKey, Option<u32>>;
 
 #[derive(Eq, PartialEq, Hash)]
 struct CacheKey {
-    start: (usize, usize),
-    dest: (usize, usize),
+    start: Pos,
+    dest: Pos,
     keys: String,
 }
 
 impl CacheKey {
-    fn new(start: (usize, usize), dest: (usize, usize), keys: &HashSet<char>) -> CacheKey {
+    fn new(start: Pos, dest: Pos, keys: &HashSet<char>) -> CacheKey {
         CacheKey {
             start,
             dest,
@@ -149,8 +233,8 @@ impl CacheKey {
 }
 
 struct Map {
-    map: HashMap<(usize, usize), Tile>,
-    starts: Vec<(usize, usize)>,
+    map: Grid<Tile>,
+    starts: Vec<Pos>,
 }
 
 impl Map {
@@ -175,46 +259,34 @@ enum Tile {
     Door(char),
 }
 
-fn parse_map(rows: Vec<String>) -> Map {
-    let mut map = HashMap::new();
-    let mut starts = Vec::new();
-    for y in 0..rows.len() {
-        let row = &rows[y];
-        for (x, c) in row.chars().enumerate() {
-            let tile = match c {
-                '.' => Tile::Empty,
-                '@' => {
-                    starts.push((x, y));
-                    Tile::Empty
-                },
-                '#' => Tile::Wall,
-                k if ('a'..='z').contains(&k) => Tile::Key(k),
-                d if ('A'..='Z').contains(&d) => Tile::Door(d),
-                _ => panic!("Unrecognized map character: {}", c)
-            };
-            map.insert((x, y), tile);
-        }
+fn parse_map(rows: Vec<String>, entrances: usize) -> Result<Map, Error> {
+    let chars = Grid::parse(&rows.join("\n"), |c| match c {
+        '.' | '@' | '#' => Some(c),
+        c if c.is_ascii_alphabetic() => Some(c),
+        _ => None
+    })?;
+    let starts: Vec<Pos> = chars.iter().filter(|&(_, &c)| c == '@').map(|(pos, _)| pos).collect();
+    if starts.len() != entrances {
+        return Err(Error::Unsolvable(format!("Expected {} entrances but the map has {}", entrances, starts.len())))
     }
-    Map { map, starts }
+    let map = chars.map(|_, &c| match c {
+        '.' | '@' => Tile::Empty,
+        '#' => Tile::Wall,
+        k if k.is_ascii_lowercase() => Tile::Key(k),
+        d => Tile::Door(d),
+    });
+    Ok(Map { map, starts })
 }
 
-fn find_key(map: &Map, key: char) -> (usize, usize) {
+fn find_key(map: &Map, key: char) -> Pos {
     if let Some(i) = key.to_digit(10) {
         map.starts[i as usize]
     } else {
-        map.map.iter()
-            .find(|(_, &tile)| {
-                match tile {
-                    Tile::Key(k) => k == key,
-                    _ => false
-                }
-            })
-            .map(|(&key_pos, _)| key_pos)
-            .expect("key not found")
+        map.map.find(|&tile| tile == Tile::Key(key)).expect("key not found")
     }
 }
 
-fn cached_pathfind(map: &Map, cache: &mut Cache, start: (usize, usize), dest: (usize, usize), held_keys: &HashSet<char>) -> Option<u32> {
+fn cached_pathfind(map: &Map, cache: &mut Cache, start: Pos, dest: Pos, held_keys: &HashSet<char>) -> Option<u32> {
     let cache_key = CacheKey::new(start, dest, held_keys);
     if !cache.contains_key(&cache_key) {
         for (dest, maybe_cost) in pathfind(map, start, held_keys) {
@@ -224,48 +296,25 @@ fn cached_pathfind(map: &Map, cache: &mut Cache, start: (usize, usize), dest: (u
     cache.get(&cache_key).copied().unwrap()
 }
 
-fn pathfind(map: &Map, start: (usize, usize), held_keys: &HashSet<char>) -> HashMap<(usize, usize), Option<u32>> {
+fn pathfind(map: &Map, start: Pos, held_keys: &HashSet<char>) -> HashMap<Pos, Option<u32>> {
     log::debug!("pathfind from {:?} with keys {:?}", start, held_keys);
-    let mut queue = VecDeque::new();
-    queue.push_back(start);
-    let mut costs = HashMap::new();
-    costs.insert(start, 0);
-    while let Some(pos) = queue.pop_front() {
-        let cost = costs[&pos];
-        for &adj in adj(pos).iter() {
-            let new_cost = cost + 1;
-            let old_cost = costs.get(&adj).copied().unwrap_or(999999999);
-            let can_move = match map.map[&adj] {
-                Tile::Empty | Tile::Key(_) => true,
-                Tile::Door(d) => held_keys.contains(&d.to_ascii_lowercase()),
-                _ => false
-            };
-            if can_move && new_cost < old_cost {
-                queue.push_back(adj);
-                costs.insert(adj, new_cost);
-            }
-        }
-    }
+    let search = search::bfs(start, |&pos| neighbors(map, pos, held_keys));
     map.map.iter()
-        .filter(|(_, &tile)| {
-            match tile {
-                Tile::Key(_) => true,
-                _ => false
-            }
-        })
+        .filter(|(_, &tile)| matches!(tile, Tile::Key(_)))
         .map(|(key_pos, _)| key_pos)
-        .chain(map.starts.iter())
-        .map(|&key_pos| (key_pos, costs.get(&key_pos).copied()))
+        .chain(map.starts.iter().copied())
+        .map(|key_pos| (key_pos, search.cost(&key_pos)))
         .collect()
 }
 
-fn adj((x, y): (usize, usize)) -> [(usize, usize);4] {
-    [
-        (x + 1, y),
-        (x, y + 1),
-        (x - 1, y),
-        (x, y - 1),
-    ]
+fn neighbors<'a>(map: &'a Map, pos: Pos, held_keys: &'a HashSet<char>) -> impl Iterator<Item = Pos> + 'a {
+    map.map.neighbors4(pos)
+        .filter(move |&(_, &tile)| match tile {
+            Tile::Empty | Tile::Key(_) => true,
+            Tile::Door(d) => held_keys.contains(&d.to_ascii_lowercase()),
+            _ => false
+        })
+        .map(|(adj, _)| adj)
 }
 
 fn set_to_string(set: &HashSet<char>) -> String {
@@ -288,8 +337,10 @@ mod test {
 #.######################
 #.....@.a.B.c.d.A.e.F.g#
 ########################";
-        let map = parse_map(parse_input(input));
+        let map = parse_map(parse_input(input).unwrap(), 1).unwrap();
         assert_eq!(Some(Some(2)), pathfind(&map, (6, 3), &HashSet::new()).get(&(8, 3)).copied());
+        assert!(matches!(parse_map(vec!["#@.?#".to_string()], 1), Err(Error::Parse { line: 1, column: Some(4), .. })));
+        assert!(matches!(parse_map(vec!["#@.@#".to_string()], 1), Err(Error::Unsolvable(_))));
     }
 
     #[test]
@@ -299,7 +350,7 @@ mod test {
 #.######################
 #.....@.a.B.c.d.A.e.F.g#
 ########################";
-        assert_eq!(132, run1(parse_input(input)));
+        assert_eq!(132, run1(parse_input(input).unwrap(), &Config::default()).unwrap());
     }
 
     #[test]
@@ -311,7 +362,7 @@ mod test {
 ###C#@#@###J#
 #fEbA.#.FgHi#
 #############";
-        assert_eq!(32, run2(parse_input(input)));
+        assert_eq!(32, run2(parse_input(input).unwrap()).unwrap());
     }
 
     #[test]
@@ -326,6 +377,6 @@ mod test {
 #o#m..#i#jk.#
 #############";
 
-        assert_eq!(72, run2(parse_input(input)));
+        assert_eq!(72, run2(parse_input(input).unwrap()).unwrap());
     }
 }
@@ -1,60 +1,160 @@
+use crate::config::Config;
+use crate::error::Error;
 use crate::intcode::*;
+use crate::parallel;
+use crate::params::{Kind, Param};
+use crate::visualize::{self, Color, Picture};
 use std::rc::Rc;
+use std::ops::Range;
 
-pub fn run1(input: Vec<String>) -> i64 {
-    let program = parse_program(&input[0]);
-    let mut sum = 0;
-    for x in 0..50 {
-        for y in 0..50 {
-            sum += test(program.clone(), x, y);
-        }
+solution!(19, 1, "Tractor Beam", "19.txt", run1, &[WIDTH, HEIGHT]);
+solution!(19, 2, "Tractor Beam", "19.txt", run2, &[SIZE]);
+
+const WIDTH: Param = Param { name: "width", default: "50", kind: Kind::Count, help: "Width of the area to scan from the emitter" };
+const HEIGHT: Param = Param { name: "height", default: "50", kind: Kind::Count, help: "Height of the area to scan from the emitter" };
+const SIZE: Param = Param { name: "size", default: "100", kind: Kind::Positive, help: "Width and height of the square the ship needs" };
+// How many times the ship's size to search down the beam before giving up on it ever being wide enough
+const MAX_DEPTH: Value = 100;
+
+// The rows are split into a band per job, each traced by its own beam from the band's first row
+pub fn run1(input: Vec<String>, config: &Config) -> Result<usize, Error> {
+    let program = read_program(&input)?;
+    let (width, height): (Value, Value) = (config.params.get(&WIDTH)?, config.params.get(&HEIGHT)?);
+    let band = (height as usize).div_ceil(config.jobs()).max(1);
+    let bands: Vec<Range<Value>> = (0..height).step_by(band).map(|y| y..(y + band as Value).min(height)).collect();
+    let counts = parallel::map(config.jobs(), bands, |ys| {
+        let mut beam = program_beam(program.clone(), ys.start);
+        (beam.count(0..width, ys), beam.probes)
+    });
+    let count = counts.iter().map(|&(count, _)| count).sum();
+    log::info!("probes: {}", counts.iter().map(|&(_, probes)| probes).sum::<usize>());
+    if visualize::enabled(config) {
+        let window = program_beam(program, 0).render(0..width, 0..height, (0, 0, 0, 0));
+        visualize::show(config, &Picture::new(&window, color).with_caption(format!("Pulled: {}", count)));
     }
-    sum
+    Ok(count)
 }
 
-pub fn run2(input: Vec<String>) -> i64 {
-    let program = parse_program(&input[0]);
-
-    let mut x = 700;
-    //let mut last_y = 0;
-    let (tlx, tly) = loop {
-        let mut y = 900;
-        loop {
-            let output = test(program.clone(), x, y);
-            if output == 1 {
-                break;
+pub fn run2(input: Vec<String>, config: &Config) -> Result<i64, Error> {
+    let mut beam = program_beam(read_program(&input)?, 0);
+    let size = config.params.get(&SIZE)?;
+    let (x, y) = beam.closest_fit(size, size, size * MAX_DEPTH)
+        .ok_or_else(|| Error::Unsolvable(format!("The beam has no room for a {0}x{0} square above row {1}", size, size * MAX_DEPTH)))?;
+    log::info!("probes: {}", beam.probes);
+    if log::log_enabled!(log::Level::Debug) || visualize::enabled(config) {
+        let window = beam.render(x - size..x + 2 * size, y - size / 2..y + size + size / 2, (x, y, size, size));
+        log::debug!("beam:\n{}", window);
+        visualize::show(config, &Picture::new(&window, color).with_caption(format!("Closest fit: {},{}", x, y)));
+    }
+    Ok(x * 10000 + y)
+}
+
+// Each row of the beam is a contiguous span of x values whose edges only move right as y grows
+struct Beam<F> {
+    probe: F,
+    // Rows above this are never traced or asked for
+    first: Value,
+    rows: Vec<Option<(Value, Value)>>,
+    probes: usize,
+}
+
+impl<F: FnMut(Value, Value) -> bool> Beam<F> {
+    fn starting_at(probe: F, first: Value) -> Beam<F> {
+        Beam {
+            probe,
+            first,
+            rows: Vec::new(),
+            probes: 0,
+        }
+    }
+
+    fn is_pulled(&mut self, x: Value, y: Value) -> bool {
+        self.probes += 1;
+        (self.probe)(x, y)
+    }
+
+    fn row(&mut self, y: Value) -> Option<(Value, Value)> {
+        while self.first + self.rows.len() as Value <= y {
+            let next = self.trace_row(self.first + self.rows.len() as Value);
+            self.rows.push(next);
+        }
+        self.rows[(y - self.first) as usize]
+    }
+
+    // Starts from the edges of the last non empty row, so each row only costs a few probes. The first
+    // row has to be searched from x = 0.
+    fn trace_row(&mut self, y: Value) -> Option<(Value, Value)> {
+        let (prev_start, prev_end) = self.rows.iter().rev().find_map(|&it| it).unwrap_or((0, 0));
+        let limit = prev_end + 10 * (y + 1);
+        let mut start = prev_start;
+        while !self.is_pulled(start, y) {
+            start += 1;
+            if start > limit {
+                return None
             }
-            y += 1;
         }
-        log::info!("tr ({}, {}) -> 1", x, y);
-        let output = test(program.clone(), x - 99, y + 99);
-        log::info!("bl ({}, {}) -> {}", x - 99, y + 99, output);
-        if validate(&program, x - 99, y) {
-            break ((x - 99), y);
+        let mut end = prev_end.max(start);
+        if !self.is_pulled(end, y) {
+            end = start;
+        }
+        while self.is_pulled(end + 1, y) {
+            end += 1;
+        }
+        Some((start, end))
+    }
+
+    fn count(&mut self, xs: Range<Value>, ys: Range<Value>) -> usize {
+        ys.filter_map(|y| self.row(y))
+            .map(|(start, end)| (end + 1).min(xs.end) - start.max(xs.start))
+            .filter(|&it| it > 0)
+            .sum::<Value>() as usize
+    }
+
+    // Top left corner of the first width x height rectangle that fits entirely in the beam, if there's
+    // one with its bottom row above max_y
+    fn closest_fit(&mut self, width: Value, height: Value, max_y: Value) -> Option<(Value, Value)> {
+        for bottom in height - 1..max_y {
+            let top = bottom - height + 1;
+            if let (Some((x, _)), Some((_, top_end))) = (self.row(bottom), self.row(top)) {
+                if top_end - x + 1 >= width {
+                    return Some((x, top))
+                }
+            }
         }
-        x += 1;
-        //last_y = y;
-    };
+        None
+    }
 
-    for y in 1050..1200 {
+    fn render(&mut self, xs: Range<Value>, ys: Range<Value>, (hx, hy, hw, hh): (Value, Value, Value, Value)) -> String {
         let mut s = String::new();
-        for x in 600..800 {
-            if (tlx..tlx+100).contains(&x) && (tly..tly+100).contains(&y) {
-                if test(program.clone(), x, y) == 1 {
-                    s.push('O');
-                } else {
-                    s.push('X')
-                }
-            } else if test(program.clone(), x, y) == 1 {
-                s.push('#');
-            } else {
-                s.push(' ');
+        for y in ys.filter(|&y| y >= 0) {
+            let row = self.row(y);
+            for x in xs.clone() {
+                let pulled = row.map(|(start, end)| (start..=end).contains(&x)).unwrap_or(false);
+                let highlighted = (hx..hx + hw).contains(&x) && (hy..hy + hh).contains(&y);
+                s.push(match (highlighted, pulled) {
+                    (true, true) => 'O',
+                    (true, false) => 'X',
+                    (false, true) => '#',
+                    (false, false) => '.',
+                });
             }
+            s.push('\n');
         }
-        println!("{}", s);
+        s
+    }
+}
+
+fn color(c: char) -> Color {
+    match c {
+        'O' => Color::Green,
+        'X' => Color::Red,
+        '#' => Color::Cyan,
+        _ => Color::Gray,
     }
+}
 
-    return tlx * 10000 + tly;
+fn program_beam(program: Program, first: Value) -> Beam<impl FnMut(Value, Value) -> bool> {
+    Beam::starting_at(move |x, y| test(program.clone(), x, y) == 1, first)
 }
 
 fn test(program: Program, x: Value, y: Value) -> Value {
@@ -69,14 +169,35 @@ fn test(program: Program, x: Value, y: Value) -> Value {
     value
 }
 
-fn validate(program: &Program, tlx: Value, tly: Value) -> bool {
-    for x in tlx..tlx+100 {
-        for y in tly..tly+100 {
-            if test(program.clone(), x, y) == 0 {
-                log::warn!("Outside beam: ({}, {})", x, y);
-                return false
-            }
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Everything between the lines x = 0.6y and x = 0.8y, plus the emitter itself
+    fn cone(x: Value, y: Value) -> bool {
+        5 * x >= 3 * y && 5 * x <= 4 * y
+    }
+
+    #[test]
+    fn count() {
+        let mut beam = Beam::starting_at(cone, 0);
+        let expected = itertools::iproduct!(0..50, 0..50).filter(|&(x, y)| cone(x, y)).count();
+        assert_eq!(expected, beam.count(0..50, 0..50));
+        let expected = itertools::iproduct!(20..30, 25..45).filter(|&(x, y)| cone(x, y)).count();
+        assert_eq!(expected, beam.count(20..30, 25..45));
+        assert_eq!(expected, Beam::starting_at(cone, 25).count(20..30, 25..45));
+    }
+
+    #[test]
+    fn fit() {
+        let mut beam = Beam::starting_at(cone, 0);
+        for &(w, h) in [(1, 1), (3, 3), (10, 10), (4, 12), (12, 4)].iter() {
+            let fits = |x: Value, y: Value| itertools::iproduct!(x..x + w, y..y + h).all(|(px, py)| cone(px, py));
+            let expected = (0..).flat_map(|y| (0..=y).map(move |x| (x, y))).find(|&(x, y)| fits(x, y)).unwrap();
+            assert_eq!(Some(expected), beam.closest_fit(w, h, 1000), "{}x{}", w, h);
         }
+        assert_eq!(None, beam.closest_fit(10, 10, 50));
+        // Never more than one wide
+        assert_eq!(None, Beam::starting_at(|x, y| x == y, 0).closest_fit(2, 2, 1000));
     }
-    return true
 }
@@ -1,19 +1,39 @@
+use crate::config::Config;
+use crate::error::Error;
 use crate::intcode::*;
+use crate::parallel;
+use crate::symbolic;
 
-pub fn run1(input: Vec<String>) -> i64 {
-    execute_program(parse_program(&input[0]), 12, 2)
+solution!(2, 1, "1202 Program Alarm", "2.txt", run1);
+solution!(2, 2, "1202 Program Alarm", "2.txt", run2, &[]);
+
+const TARGET: Value = 19690720;
+
+pub fn run1(input: Vec<String>) -> Result<i64, Error> {
+    Ok(execute_program(read_program(&input)?, 12, 2))
 }
 
-pub fn run2(input: Vec<String>) -> i64 {
-    let original = parse_program(&input[0]);
-    let target = 19690720;
-    for (n, v) in itertools::iproduct!(0..100, 0..100) {
-        let output = execute_program(original.clone(), n, v);
-        if output == target {
-            return 100 * n + v
-        }
-    }
-    panic!("Could not find target!");
+pub fn run2(input: Vec<String>, config: &Config) -> Result<i64, Error> {
+    let original = read_program(&input)?;
+    let (n, v) = solve_symbolically(&original, TARGET).unwrap_or_else(|e| {
+        log::info!("Falling back to brute force: {}", e);
+        brute_force(&original, TARGET, config.jobs())
+    }).ok_or_else(|| Error::Unsolvable(format!("No noun and verb from 0 to 99 give {}", TARGET)))?;
+    Ok(100 * n + v)
+}
+
+// Ok(None) means the target is provably unreachable
+fn solve_symbolically(program: &Program, target: Value) -> Result<Option<(Value, Value)>, String> {
+    let memory = symbolic::execute(program, &[1, 2], 10_000)?;
+    let result = memory[0].as_ref().ok_or("Output depends on the symbols in an unknown way")?;
+    log::info!("program[0] = {}", result.format(&["noun", "verb"]));
+    Ok(symbolic::solve(result, target, &[0..100, 0..100]).map(|it| (it[0], it[1])))
+}
+
+fn brute_force(program: &Program, target: Value, jobs: usize) -> Option<(Value, Value)> {
+    parallel::find_map(jobs, itertools::iproduct!(0..100, 0..100).collect(), |(n, v)| {
+        Some((n, v)).filter(|_| execute_program(program.clone(), n, v) == target)
+    })
 }
 
 fn execute_program(mut program: Program, n: i64, v: i64) -> i64 {
@@ -22,3 +42,18 @@ fn execute_program(mut program: Program, n: i64, v: i64) -> i64 {
     execute_no_io(&mut program);
     program[0]
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn symbolic_matches_brute_force() {
+        // [3] = [noun] + [verb], then [0] = noun * verb + [13]
+        let program = parse_program("1,0,0,3,2,1,2,0,1,0,13,0,99,7").unwrap();
+        assert_eq!(Ok(Some((1, 35))), solve_symbolically(&program, 42));
+        assert_eq!(Some((1, 35)), brute_force(&program, 42, 2));
+        assert_eq!(Ok(None), solve_symbolically(&program, 100 * 100));
+        assert!(matches!(run2(vec!["1,0,0,0,99".to_string()], &Config::default()), Err(Error::Unsolvable(_))));
+    }
+}
@@ -1,257 +1,219 @@
-use std::collections::{HashMap, VecDeque};
+use crate::error::Error;
+use crate::grid::{Grid, Pos};
+use crate::search;
+use std::collections::HashMap;
 
-pub fn run1(input: Vec<String>) -> u32 {
-    let map = Map::from_input(input);
+solution!(20, 1, "Donut Maze", "20.txt", run1);
+solution!(20, 2, "Donut Maze", "20.txt", run2);
+
+pub fn run1(input: Vec<String>) -> Result<u32, Error> {
+    let map = Map::from_input(input)?;
 
     pathfind(&map.chars, &map.outside_portals, &map.inside_portals)
 }
 
-pub fn run2(input: Vec<String>) -> u32 {
-    let map = Map::from_input(input);
+pub fn run2(input: Vec<String>) -> Result<u32, Error> {
+    let map = Map::from_input(input)?;
 
     pathfind2(&map.chars, &map.outside_portals, &map.inside_portals)
 }
 
 struct Map {
-    chars: HashMap<(usize, usize), char>,
-    outside_portals: HashMap<(usize, usize), String>,
-    inside_portals: HashMap<(usize, usize), String>,
+    chars: Grid<char>,
+    outside_portals: HashMap<Pos, String>,
+    inside_portals: HashMap<Pos, String>,
 }
 
 impl Map {
-    fn from_input(input: Vec<String>) -> Map {
-        let width = input[0].len() - 4;
-        let height = input.len() - 4;
+    fn from_input(input: Vec<String>) -> Result<Map, Error> {
+        let chars = Grid::parse(&input.join("\n"), Some)?;
+        let width = input.first().map_or(0, String::len) as i64 - 4;
+        let height = input.len() as i64 - 4;
         let thicc = input.iter()
             .skip(2)
             .enumerate()
-            .find(|(_, row)| {
-                let l = row.len();
-                row[2..l-2].contains(' ')
-            })
-            .map(|(i, _)| i)
-            .expect("Expected a donut");
-
-        let mut chars = HashMap::new();
-        for (y, row) in input.iter().enumerate() {
-            for (x, c) in row.chars().enumerate() {
-                chars.insert((x, y), c);
-            }
-        }
+            .find(|(_, row)| row.get(2..row.len().saturating_sub(2)).is_some_and(|it| it.contains(' ')))
+            .map(|(i, _)| i as i64)
+            .ok_or_else(|| Error::Unsolvable("The maze has no hole in the middle".to_string()))?;
 
         let mut outside_portals = HashMap::new();
         let mut inside_portals = HashMap::new();
         // top outside
         for x in 2..2+width {
             let y = 2;
-            if chars[&(x, y)] == '.' {
+            if chars[(x, y)] == '.' {
                 let mut s = String::new();
-                s.push(chars[&(x, y-2)]);
-                s.push(chars[&(x, y-1)]);
+                s.push(chars[(x, y-2)]);
+                s.push(chars[(x, y-1)]);
                 outside_portals.insert((x, y), s);
             }
         }
         // bottom outside
         for x in 2..2+width {
             let y = height+1;
-            if chars[&(x, y)] == '.' {
+            if chars[(x, y)] == '.' {
                 let mut s = String::new();
-                s.push(chars[&(x, y+1)]);
-                s.push(chars[&(x, y+2)]);
+                s.push(chars[(x, y+1)]);
+                s.push(chars[(x, y+2)]);
                 outside_portals.insert((x, y), s);
             }
         }
         // top inside
         for x in 2+thicc..2+width-thicc {
             let y = thicc+1;
-            if chars[&(x, y)] == '.' {
+            if chars[(x, y)] == '.' {
                 let mut s = String::new();
-                s.push(chars[&(x, y+1)]);
-                s.push(chars[&(x, y+2)]);
+                s.push(chars[(x, y+1)]);
+                s.push(chars[(x, y+2)]);
                 inside_portals.insert((x, y), s);
             }
         }
         // bottom inside
         for x in 2+thicc..2+width-thicc {
             let y = height-thicc+2;
-            if chars[&(x, y)] == '.' {
+            if chars[(x, y)] == '.' {
                 let mut s = String::new();
-                s.push(chars[&(x, y-2)]);
-                s.push(chars[&(x, y-1)]);
+                s.push(chars[(x, y-2)]);
+                s.push(chars[(x, y-1)]);
                 inside_portals.insert((x, y), s);
             }
         }
         // left outside
         for y in 2..2+height {
             let x = 2;
-            if chars[&(x, y)] == '.' {
+            if chars[(x, y)] == '.' {
                 let mut s = String::new();
-                s.push(chars[&(x-2, y)]);
-                s.push(chars[&(x-1, y)]);
+                s.push(chars[(x-2, y)]);
+                s.push(chars[(x-1, y)]);
                 outside_portals.insert((x, y), s);
             }
         }
         // right outside
         for y in 2..2+height {
             let x = width+1;
-            if chars[&(x, y)] == '.' {
+            if chars[(x, y)] == '.' {
                 let mut s = String::new();
-                s.push(chars[&(x+1, y)]);
-                s.push(chars[&(x+2, y)]);
+                s.push(chars[(x+1, y)]);
+                s.push(chars[(x+2, y)]);
                 outside_portals.insert((x, y), s);
             }
         }
         // left inside
         for y in 2+thicc..2+height-thicc {
             let x = thicc+1;
-            if chars[&(x, y)] == '.' {
+            if chars[(x, y)] == '.' {
                 let mut s = String::new();
-                s.push(chars[&(x+1, y)]);
-                s.push(chars[&(x+2, y)]);
+                s.push(chars[(x+1, y)]);
+                s.push(chars[(x+2, y)]);
                 inside_portals.insert((x, y), s);
             }
         }
         // right inside
         for y in 2+thicc..2+height-thicc {
             let x = width-thicc+2;
-            if chars[&(x, y)] == '.' {
+            if chars[(x, y)] == '.' {
                 let mut s = String::new();
-                s.push(chars[&(x-2, y)]);
-                s.push(chars[&(x-1, y)]);
+                s.push(chars[(x-2, y)]);
+                s.push(chars[(x-1, y)]);
                 inside_portals.insert((x, y), s);
             }
         }
 
-        assert_eq!(outside_portals.len(), inside_portals.len() + 2);
+        // Every portal but AA and ZZ comes in an outside and inside pair
+        let unpaired = outside_portals.values()
+            .filter(|&name| name != "AA" && name != "ZZ" && !inside_portals.values().any(|it| it == name))
+            .chain(inside_portals.values().filter(|&name| !outside_portals.values().any(|it| it == name)))
+            .next();
+        if let Some(name) = unpaired {
+            return Err(Error::Unsolvable(format!("Portal {} has no other end", name)))
+        }
+        for name in ["AA", "ZZ"] {
+            if !outside_portals.values().any(|it| it == name) {
+                return Err(Error::Unsolvable(format!("The maze has no {} on the outside", name)))
+            }
+        }
 
-        Map { chars, outside_portals, inside_portals }
+        Ok(Map { chars, outside_portals, inside_portals })
     }
 }
 
-fn pathfind(
-    chars: &HashMap<(usize, usize), char>,
-    outside_portals: &HashMap<(usize, usize), String>,
-    inside_portals: &HashMap<(usize, usize), String>,
-) -> u32 {
-    let start = outside_portals.iter().find(|it| it.1 == "AA").map(|(&pos, _)| pos).unwrap();
-    let mut queue = VecDeque::new();
-    queue.push_back(start);
-    let mut costs = HashMap::new();
-    costs.insert(start, 0);
-    /*let mut outside_d = HashMap::new(); // dist from start to outside portal
-    let mut inside_d = HashMap::new();*/
-    while let Some(pos) = queue.pop_front() {
-        let cost = costs[&pos];
-        if let Some(p) = inside_portals.get(&pos) {
-            let (adj, _) = outside_portals.iter()
+// Where a step from pos can go: through a portal, with the change in level, or to an adjacent open tile
+fn moves(
+    chars: &Grid<char>,
+    outside_portals: &HashMap<Pos, String>,
+    inside_portals: &HashMap<Pos, String>,
+    pos: Pos,
+) -> Vec<(Pos, i32)> {
+    let mut moves = Vec::new();
+    if let Some(p) = inside_portals.get(&pos) {
+        let (&dest, _) = outside_portals.iter()
+            .find(|(_, op)| *op == p)
+            .unwrap();
+        moves.push((dest, 1));
+    } else if let Some(p) = outside_portals.get(&pos) {
+        if p != "ZZ" && p != "AA" {
+            let (&dest, _) = inside_portals.iter()
                 .find(|(_, op)| *op == p)
-                .unwrap();
-            let new_cost = cost + 1;
-            let old_cost = costs.get(adj).copied().unwrap_or(999999999);
-            if new_cost < old_cost {
-                queue.push_back(*adj);
-                costs.insert(*adj, new_cost);
-            }
-        } else if let Some(p) = outside_portals.get(&pos) {
-            if p != "ZZ" && p != "AA" {
-                let (adj, _) = inside_portals.iter()
-                    .find(|(_, op)| *op == p)
-                    .expect("Expected to find a matching inside portal");
-                let new_cost = cost + 1;
-                let old_cost = costs.get(adj).copied().unwrap_or(999999999);
-                if new_cost < old_cost {
-                    queue.push_back(*adj);
-                    costs.insert(*adj, new_cost);
-                }
-            }
-        }
-        for adj in adj(pos).iter() {
-            let new_cost = cost + 1;
-            let old_cost = costs.get(adj).copied().unwrap_or(999999999);
-            let can_move = chars[adj] == '.';
-            if can_move && new_cost < old_cost {
-                queue.push_back(*adj);
-                costs.insert(*adj, new_cost);
-            }
+                .expect("Expected to find a matching inside portal");
+            moves.push((dest, -1));
         }
     }
+    moves.extend(chars.neighbors4(pos).filter(|&(_, &c)| c == '.').map(|(dest, _)| (dest, 0)));
+    moves
+}
+
+fn pathfind(
+    chars: &Grid<char>,
+    outside_portals: &HashMap<Pos, String>,
+    inside_portals: &HashMap<Pos, String>,
+) -> Result<u32, Error> {
+    let start = outside_portals.iter().find(|it| it.1 == "AA").map(|(&pos, _)| pos).unwrap();
+    let search = search::bfs(start, |&pos| {
+        moves(chars, outside_portals, inside_portals, pos).into_iter().map(|(dest, _)| dest)
+    });
 
     let portal_costs: HashMap<_, _> = outside_portals.iter()
-        .map(|(pos, name)| (name, costs.get(pos)))
+        .map(|(pos, name)| (name, search.cost(pos)))
         .collect();
     log::info!("{:#?}", portal_costs);
 
     outside_portals.iter()
         .find(|it| it.1 == "ZZ")
-        .map(|(zz_pos, _)| costs[zz_pos])
-        .expect("Epected to find ZZ")
+        .and_then(|(zz_pos, _)| search.cost(zz_pos))
+        .ok_or_else(|| Error::Unsolvable("There's no path from AA to ZZ".to_string()))
 }
 
+// The outermost level is 0 and there's nothing beyond it
 fn pathfind2(
-        chars: &HashMap<(usize, usize), char>,
-        outside_portals: &HashMap<(usize, usize), String>,
-        inside_portals: &HashMap<(usize, usize), String>,
-) -> u32 {
+        chars: &Grid<char>,
+        outside_portals: &HashMap<Pos, String>,
+        inside_portals: &HashMap<Pos, String>,
+) -> Result<u32, Error> {
     let start = outside_portals.iter().find(|it| it.1 == "AA").map(|(&pos, _)| pos).unwrap();
     let goal = outside_portals.iter()
         .find(|it| it.1 == "ZZ")
         .map(|(&zz_pos, _)| zz_pos)
         .expect("Epected to find ZZ");
-    let mut queue = VecDeque::new();
-    queue.push_back((start, 0));
-    let mut costs = HashMap::new();
-    costs.insert((start, 0), 0);
-    while let Some(current) = queue.pop_front() {
-        let cost = costs[&current];
-        if current == (goal, 0) {
-            return cost;
-        }
-        let (pos, layer) = current;
-        if let Some(p) = inside_portals.get(&pos) {
-            let (&dest, _) = outside_portals.iter()
-                .find(|(_, op)| *op == p)
-                .unwrap();
-            let adj = (dest, layer + 1);
-            let new_cost = cost + 1;
-            let old_cost = costs.get(&adj).copied().unwrap_or(999999999);
-            if new_cost < old_cost {
-                queue.push_back(adj);
-                costs.insert(adj, new_cost);
-            }
-        } else if let Some(p) = outside_portals.get(&pos) {
-            if layer > 0 && p != "ZZ" && p != "AA" {
-                let (&dest, _) = inside_portals.iter()
-                    .find(|(_, op)| *op == p)
-                    .expect("Expected to find a matching inside portal");
-                let adj = (dest, layer - 1);
-                let new_cost = cost + 1;
-                let old_cost = costs.get(&adj).copied().unwrap_or(999999999);
-                if new_cost < old_cost {
-                    queue.push_back(adj);
-                    costs.insert(adj, new_cost);
-                }
-            }
-        }
-        for &dest in adj(pos).iter() {
-            let adj = (dest, layer);
-            let new_cost = cost + 1;
-            let old_cost = costs.get(&adj).copied().unwrap_or(999999999);
-            let can_move = chars[&dest] == '.';
-            if can_move && new_cost < old_cost {
-                queue.push_back(adj);
-                costs.insert(adj, new_cost);
-            }
-        }
-    }
-
-    unreachable!("Ran out of places to go")
+    let search = search::bfs_until(
+        vec![(start, 0)],
+        |&(pos, layer)| moves(chars, outside_portals, inside_portals, pos).into_iter()
+            .map(move |(dest, change)| (dest, layer + change))
+            .filter(|&(_, layer)| layer >= 0),
+        |&current| current == (goal, 0),
+    );
+    search.goal_cost().ok_or_else(|| Error::Unsolvable("There's no path from AA to ZZ".to_string()))
 }
 
-fn adj((x, y): (usize, usize)) -> [(usize, usize);4] {
-    [
-        (x + 1, y),
-        (x, y + 1),
-        (x - 1, y),
-        (x, y - 1),
-    ]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_input;
+
+    #[test]
+    fn not_a_donut() {
+        let input = parse_input("#######\n#######\n#######\n#######\n#######").unwrap();
+        assert!(matches!(run1(input), Err(Error::Unsolvable(_))));
+        let input = parse_input("###\n##").unwrap();
+        assert!(matches!(run1(input), Err(Error::Parse { line: 2, column: None, .. })));
+    }
 }
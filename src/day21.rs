@@ -1,7 +1,11 @@
+use crate::error::Error;
 use crate::intcode::*;
 use std::rc::Rc;
 
-pub fn run1(input: Vec<String>) -> Value {
+solution!(21, 1, "Springdroid Adventure", "21.txt", run1);
+solution!(21, 2, "Springdroid Adventure", "21.txt", run2);
+
+pub fn run1(input: Vec<String>) -> Result<Value, Error> {
     let js = "\
 NOT A T
 OR T J
@@ -15,7 +19,7 @@ WALK
     execute_js(input, js)
 }
 
-pub fn run2(input: Vec<String>) -> Value {
+pub fn run2(input: Vec<String>) -> Result<Value, Error> {
     let js = "\
 NOT A T
 OR T J
@@ -33,26 +37,28 @@ RUN
     execute_js(input, js)
 }
 
-fn execute_js(prog: Vec<String>, js: &str) -> Value {
-    let mut comp = Computer::new(parse_program(&prog[0]));
+fn execute_js(prog: Vec<String>, js: &str) -> Result<Value, Error> {
+    let mut comp = Computer::new(read_program(&prog)?);
     let input = Stream::new_wrapped();
     let output = Stream::new_wrapped();
     comp.set_input(Some(Rc::clone(&input)));
     comp.set_output(Some(Rc::clone(&output)));
 
-    assert_eq!(ComputerState::WaitingOnInput, comp.execute());
+    if comp.execute() != ComputerState::WaitingOnInput {
+        return Err(Error::Unsolvable("The springdroid didn't ask for instructions".to_string()))
+    }
     output.borrow_mut().read_all();
 
     input.borrow_mut().write_all(&to_input(js));
     comp.execute();
 
     let out = output.borrow_mut().read_all();
-    let last = out.last().copied().unwrap();
-    if last > 127 {
-        last
-    } else {
-        println!("{}", to_string(&out));
-        panic!("Oopsy woopsy");
+    match out.last().copied() {
+        Some(damage) if damage > 127 => Ok(damage),
+        _ => {
+            log::debug!("{}", to_string(&out));
+            Err(Error::Unsolvable("The springdroid fell into space".to_string()))
+        }
     }
 }
 
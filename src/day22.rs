@@ -1,20 +1,50 @@
 use std::collections::VecDeque;
 use std::str::FromStr;
-use std::num::ParseIntError;
+use crate::config::Config;
+use crate::error::{self, Error, ParseError};
 use crate::day22::Technique::*;
+use crate::math;
+use crate::params::{Kind, Param};
+
+solution!(22, 1, "Slam Shuffle", "22.txt", run1, &[SMALL_DECK, CARD]);
+solution!(22, 2, "Slam Shuffle", "22.txt", run2, &[BIG_DECK, POSITION, SHUFFLES]);
+
+const SMALL_DECK: Param = Param { name: "deck", default: "10007", kind: Kind::Positive, help: "Cards in the deck" };
+const CARD: Param = Param { name: "card", default: "2019", kind: Kind::Count, help: "Card to find the position of" };
+const BIG_DECK: Param = Param { name: "deck", default: "119315717514047", kind: Kind::Positive, help: "Cards in the deck, which should be prime" };
+const POSITION: Param = Param { name: "position", default: "2020", kind: Kind::Count, help: "Position to find the card at" };
+const SHUFFLES: Param = Param { name: "shuffles", default: "101741582076661", kind: Kind::Positive, help: "Times the whole shuffle is repeated" };
+
+pub fn run1(input: Vec<Technique>, config: &Config) -> Result<usize, Error> {
+    let (deck_size, card) = (config.params.get(&SMALL_DECK)?, config.params.get(&CARD)?);
+    check(&input, deck_size, card)?;
+    let deck = shuffle(deck_size, input);
+    Ok(deck.iter().position(|&v| v == card).unwrap())
+}
 
-use modinverse::modinverse;
-
-pub fn run1(input: Vec<Technique>) -> usize {
-    let deck = shuffle(10007, input);
-    deck.iter().enumerate().find(|&(_, &v)| v == 2019).unwrap().0
+pub fn run2(input: Vec<Technique>, config: &Config) -> Result<i64, Error> {
+    let (deck_size, position) = (config.params.get(&BIG_DECK)?, config.params.get(&POSITION)?);
+    check(&input, deck_size, position)?;
+    Ok(lazy_shuffle(deck_size, position, &input, config.params.get(&SHUFFLES)?))
 }
 
-pub fn run2(input: Vec<Technique>) -> u64 {
-    lazy_shuffle(119315717514047, 2020, &input, 101741582076661)
+// The card or position has to be in the deck, and dealing with an increment that shares a factor with
+// the deck size would put several cards in the same place
+fn check(input: &[Technique], deck_size: i64, i: i64) -> Result<(), Error> {
+    if i >= deck_size {
+        return Err(Error::Unsolvable(format!("{} is past the end of a {} card deck", i, deck_size)))
+    }
+    let shared = input.iter().find_map(|t| match t {
+        &Increment(n) if math::gcd(n as i64, deck_size) != 1 => Some(n),
+        _ => None
+    });
+    match shared {
+        Some(n) => Err(Error::Unsolvable(format!("Can't deal with increment {} into a {} card deck, they share a factor", n, deck_size))),
+        None => Ok(()),
+    }
 }
 
-fn shuffle(deck_size: u64, input: Vec<Technique>) -> Vec<u64> {
+fn shuffle(deck_size: i64, input: Vec<Technique>) -> Vec<i64> {
     let mut deck = VecDeque::new();
     for x in 0..deck_size {
         deck.push_back(x);
@@ -29,10 +59,10 @@ fn shuffle(deck_size: u64, input: Vec<Technique>) -> Vec<u64> {
     deck.into()
 }
 
-fn lazy_shuffle(deck_size: u64, i: u64, input: &Vec<Technique>, times: u64) -> u64 {
+fn lazy_shuffle(deck_size: i64, i: i64, input: &[Technique], times: u64) -> i64 {
     let (a, b) = repeat(fold_techniques(input, deck_size), deck_size, times);
     log::info!("a = {}, b = {}", a, b);
-    (a*i + b) % deck_size
+    (math::mod_mul(a, i, deck_size) + b) % deck_size
 }
 
 #[derive(Debug, Clone)]
@@ -43,60 +73,51 @@ pub enum Technique {
 }
 
 impl FromStr for Technique {
-    type Err = String;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const CUT: &str = "cut ";
+        const INCREMENT: &str = "deal with increment ";
         if s == "deal into new stack" {
             Ok(NewStack)
-        } else if s.starts_with("cut") {
-            let n = s.split(' ')
-                .skip(1)
-                .next()
-                .ok_or_else(|| "need a number for cut".to_string())?
-                .parse()
-                .map_err(|e: ParseIntError| e.to_string())?;
-            Ok(Cut(n))
-        } else if s.starts_with("deal with increment") {
-            let n = s.split(' ')
-                .skip(3)
-                .next()
-                .ok_or_else(|| "need a number for cut".to_string())?
-                .parse()
-                .map_err(|e: ParseIntError| e.to_string())?;
-            Ok(Increment(n))
+        } else if let Some(n) = s.strip_prefix(CUT) {
+            Ok(Cut(error::parse_number(n, CUT.len() + 1)?))
+        } else if let Some(n) = s.strip_prefix(INCREMENT) {
+            Ok(Increment(error::parse_number(n, INCREMENT.len() + 1)?))
         } else {
-            Err(format!("Unrecognized technique: {}", s))
+            Err(ParseError::at(1, format!("unrecognized technique '{}'", s)))
         }
     }
 }
 
 impl Technique {
-    fn execute(&self, deck: &mut VecDeque<u64>) {
+    fn execute(&self, deck: &mut VecDeque<i64>) {
         let size = deck.len();
         let mut vec = Vec::with_capacity(size);
-        let size = size as u64;
+        let size = size as i64;
         for i in 0..size {
             let (a, b) = self.coefs(size);
-            let di = (a*i + b) % size;
+            let di = (math::mod_mul(a, i, size) + b) % size;
             vec.push(deck[di as usize]);
         }
         *deck = vec.into()
     }
 
-    fn coefs(&self, size: u64) -> (u64, u64) {
+    // The position in the previous deck of the card now at position i is (a*i + b) % size
+    fn coefs(&self, size: i64) -> (i64, i64) {
         match self {
             NewStack => (size - 1, size - 1),
-            &Cut(n) => (1, (n + size as i64) as u64 % size),
-            &Increment(n) => (modinverse(n as i64, size as i64).unwrap() as u64, 0),
+            &Cut(n) => (1, n.rem_euclid(size)),
+            &Increment(n) => (math::mod_inverse(n as i64, size).expect("increment must be coprime with the deck size"), 0),
         }
     }
 }
 
-fn fold_techniques(techs: &Vec<Technique>, size: u64) -> (u64, u64) {
+fn fold_techniques(techs: &[Technique], size: i64) -> (i64, i64) {
     techs.iter().fold((1, 0), |cs, t| compose(cs, t.coefs(size), size))
 }
 
-fn repeat(base: (u64, u64), size: u64, times: u64) -> (u64, u64) {
+fn repeat(base: (i64, i64), size: i64, times: u64) -> (i64, i64) {
     if times == 1 {
         return base
     }
@@ -110,17 +131,11 @@ fn repeat(base: (u64, u64), size: u64, times: u64) -> (u64, u64) {
     compose(cs, c2s, size)
 }
 
-fn compose(first: (u64, u64), second: (u64, u64), size: u64) -> (u64, u64) {
+fn compose(first: (i64, i64), second: (i64, i64), size: i64) -> (i64, i64) {
     // c(ai + b) + d = aci + cb + d
     let (a, b) = second;
-    let a = a as u128;
-    let b = b as u128;
     let (c, d) = first;
-    let c = c as u128;
-    let d = d as u128;
-    let newa = (a * c) % (size as u128);
-    let newb = (c*b + d) % (size as u128);
-    let result = (newa as u64, newb as u64);
+    let result = (math::mod_mul(a, c, size), (math::mod_mul(c, b, size) + d) % size);
     log::debug!("compose({:?}, {:?}) = {:?}", first, second, result);
     result
 }
@@ -163,7 +178,7 @@ mod test {
         let input = "deal with increment 7
 deal into new stack
 deal into new stack";
-        assert_eq!(vec![0, 3, 6, 9, 2, 5, 8, 1, 4, 7], shuffle(10, parse_input(input)));
+        assert_eq!(vec![0, 3, 6, 9, 2, 5, 8, 1, 4, 7], shuffle(10, parse_input(input).unwrap()));
     }
 
     #[test]
@@ -171,7 +186,7 @@ deal into new stack";
         let input = "cut 6
 deal with increment 7
 deal into new stack";
-        assert_eq!(vec![3, 0, 7, 4, 1, 8, 5, 2, 9, 6], shuffle(10, parse_input(input)));
+        assert_eq!(vec![3, 0, 7, 4, 1, 8, 5, 2, 9, 6], shuffle(10, parse_input(input).unwrap()));
     }
 
     #[test]
@@ -179,7 +194,7 @@ deal into new stack";
         let input = "cut 6
 deal with increment 7
 deal into new stack";
-        assert_eq!((7, 3), fold_techniques(&parse_input(input), 10));
+        assert_eq!((7, 3), fold_techniques(&parse_input(input).unwrap(), 10));
         // 0 1 2 3 4 5 6 7 8 9 = 1i + 0
         // 6 7 8 9 0 1 2 3 4 5 = 1(1i + 6) + 0 = 1*1i + 1*6 + 0 = 1i + 6
         // 6 9 2 5 8 1 4 7 0 3 = 1*(modinv(7, 10)*i) + 6 = 1*3*i + 6 = 3i + 6
@@ -191,7 +206,7 @@ deal into new stack";
         let input = "deal with increment 7
 deal with increment 9
 cut -2";
-        assert_eq!(vec![6, 3, 0, 7, 4, 1, 8, 5, 2, 9], shuffle(10, parse_input(input)));
+        assert_eq!(vec![6, 3, 0, 7, 4, 1, 8, 5, 2, 9], shuffle(10, parse_input(input).unwrap()));
         // 1i + 0
         // 1*(modinv(7, 10)*i) + 0 = 3i + 0
         // 3*(modinv(9, 10)*i) + 0 = 3*9*i + 0 = 27i + 0 = 7i + 0
@@ -210,6 +225,14 @@ cut 3
 deal with increment 9
 deal with increment 3
 cut -1";
-        assert_eq!(vec![9, 2, 5, 8, 1, 4, 7, 0, 3, 6], shuffle(10, parse_input(input)));
+        assert_eq!(vec![9, 2, 5, 8, 1, 4, 7, 0, 3, 6], shuffle(10, parse_input(input).unwrap()));
+    }
+
+    #[test]
+    fn checks() {
+        let input: Vec<Technique> = parse_input("deal with increment 7\ncut 3").unwrap();
+        assert_eq!(Ok(()), check(&input, 10, 9));
+        assert_eq!(Err(Error::Unsolvable(String::from("10 is past the end of a 10 card deck"))), check(&input, 10, 10));
+        assert_eq!(Err(Error::Unsolvable(String::from("Can't deal with increment 7 into a 14 card deck, they share a factor"))), check(&input, 14, 0));
     }
 }
@@ -1,96 +1,311 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::export;
 use crate::intcode::*;
+use crate::supervisor::{Status, Supervisor};
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
 
-pub fn run1(input: Vec<String>) -> Value {
-    let mut comps = Vec::new();
-    let out_s = Stream::new_wrapped();
-    for i in 0..50 {
-        let mut comp = Computer::new(parse_program(&input[0]));
-        let in_s = Stream::new_wrapped();
-        in_s.borrow_mut().write(i);
-        comp.set_input(Some(in_s));
-        comp.set_output(Some(Rc::clone(&out_s)));
-        comps.push(comp);
-    }
-    let mut router = Router::new(out_s);
-    loop {
-        if let &Some((_, y)) = &router.nat {
-            return y;
-        }
-        for current in 0..50 {
-            log::debug!("Executing comp {}", current);
-            if comps[current].execute() == ComputerState::WaitingOnInput {
-                comps[current].input().unwrap().borrow_mut().write(-1);
-            }
+solution!(23, 1, "Category Six", "23.txt", run1, &[]);
+solution!(23, 2, "Category Six", "23.txt", run2, &[]);
+
+const NODES: usize = 50;
+const NAT: Value = 255;
+
+// Any lines after the program are a capture from a previous run, which is replayed instead of routing live traffic
+pub fn run1(input: Vec<String>, config: &Config) -> Result<Value, Error> {
+    let mut network = Network::new(&input, config)?;
+    let result = loop {
+        network.round();
+        if let Some((_, y)) = network.router.nat {
+            break y;
         }
-        router.route(&comps);
-    }
+        match network.supervisor.status() {
+            Status::Running => {}
+            status => return Err(stopped(status)),
+        }
+    };
+    network.router.finish("23-1.cap", config);
+    Ok(result)
 }
 
-pub fn run2(input: Vec<String>) -> Value {
-    let mut comps = Vec::new();
-    let out_s = Stream::new_wrapped();
-    for i in 0..50 {
-        let mut comp = Computer::new(parse_program(&input[0]));
-        let in_s = Stream::new_wrapped();
-        in_s.borrow_mut().write(i);
-        comp.set_input(Some(in_s));
-        comp.set_output(Some(Rc::clone(&out_s)));
-        comps.push(comp);
-    }
-    let mut router = Router::new(out_s);
+pub fn run2(input: Vec<String>, config: &Config) -> Result<Value, Error> {
+    let mut network = Network::new(&input, config)?;
     let mut last_nat_packet_y = None;
-    let mut first = true; // HACK: for some reason the first round is idle
-    loop {
-        for current in 0..50 {
-            log::debug!("Executing comp {}", current);
-            if comps[current].execute() == ComputerState::WaitingOnInput {
-                comps[current].input().unwrap().borrow_mut().write(-1);
+    let result = loop {
+        network.round();
+        match network.supervisor.status() {
+            Status::Running => {}
+            Status::Idle => {
+                let y = network.router.wake(network.supervisor.computers())?;
+                if last_nat_packet_y == Some(y) {
+                    break y;
+                }
+                last_nat_packet_y = Some(y)
             }
+            status => return Err(stopped(status)),
         }
-        if router.route(&comps) && !first {
-            let nat_packet = router.nat.clone();
-            let (x, y) = nat_packet.expect("Expected nat to have a packet when network is idle");
-            if last_nat_packet_y.is_some() && last_nat_packet_y.unwrap() == y {
-                return y;
-            }
-            comps[0].input().unwrap().borrow_mut().write_all(&[x, y]);
-            last_nat_packet_y = Some(y)
+    };
+    network.router.finish("23-2.cap", config);
+    Ok(result)
+}
+
+fn stopped(status: Status) -> Error {
+    Error::Unsolvable(match status {
+        Status::Halted => format!("All {} nodes halted", NODES),
+        Status::Idle => "The network went idle before anything was sent to the NAT".to_string(),
+        Status::Deadlocked(blocked) => format!("The network deadlocked: {}", blocked.iter().map(|it| it.to_string()).collect::<Vec<_>>().join(", ")),
+        Status::Running => unreachable!("The network is still running"),
+    })
+}
+
+struct Network {
+    supervisor: Supervisor,
+    router: Router,
+}
+
+impl Network {
+    fn new(input: &[String], config: &Config) -> Result<Network, Error> {
+        let mut supervisor = Supervisor::polling(-1);
+        let program = read_program(input)?;
+        let mut outputs = Vec::new();
+        for i in 0..NODES {
+            let mut comp = Computer::new(program.clone());
+            let in_s = Stream::new_wrapped();
+            in_s.borrow_mut().write(i as Value);
+            let out_s = Stream::new_wrapped();
+            comp.set_input(Some(in_s));
+            comp.set_output(Some(Rc::clone(&out_s)));
+            supervisor.add(&i.to_string(), comp);
+            outputs.push(out_s);
+        }
+        let mut router = Router::new(outputs, export::enabled(config));
+        if input.len() > 1 {
+            let capture = parse_capture(&input[1..])?;
+            log::info!("Replaying {} packets", capture.len());
+            router.replay = Some(capture.into());
+        }
+        Ok(Network { supervisor, router })
+    }
+
+    fn round(&mut self) {
+        self.supervisor.round();
+        self.router.route(self.supervisor.computers());
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Packet {
+    round: usize,
+    source: Value,
+    destination: Value,
+    x: Value,
+    y: Value,
+}
+
+// One packet per line: round source destination x y
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {} {} {}", self.round, self.source, self.destination, self.x, self.y)
+    }
+}
+
+impl FromStr for Packet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!("Expected 5 fields in packet '{}'", s))
         }
-        first = false;
+        let round = fields[0].parse().map_err(|e| format!("Bad round in '{}': {}", s, e))?;
+        let values: Vec<Value> = fields[1..].iter()
+            .map(|it| it.parse().map_err(|e| format!("Bad value in '{}': {}", s, e)))
+            .collect::<Result<_, _>>()?;
+        Ok(Packet { round, source: values[0], destination: values[1], x: values[2], y: values[3] })
     }
 }
 
+// The lines after the program, so the first is line 2 of the input
+fn parse_capture(lines: &[String]) -> Result<Vec<Packet>, Error> {
+    lines.iter()
+        .enumerate()
+        .map(|(i, it)| (i, it.trim()))
+        .filter(|(_, it)| !it.is_empty() && !it.starts_with('#'))
+        .map(|(i, line)| {
+            let error = |reason| Error::Parse { file: None, line: i + 2, column: None, text: line.to_string(), reason };
+            let packet: Packet = line.parse().map_err(error)?;
+            if !is_address(packet.source) || !is_address(packet.destination) {
+                return Err(error(format!("addresses go from 0 to {} or are the NAT's {}", NODES - 1, NAT)))
+            }
+            Ok(packet)
+        })
+        .collect()
+}
+
+fn is_address(address: Value) -> bool {
+    address == NAT || (0..NODES as Value).contains(&address)
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+struct NodeStats {
+    sent: usize,
+    received: usize,
+    idle_rounds: usize,
+}
+
 struct Router {
-    stream: Rc<RefCell<Stream>>,
+    outputs: Vec<Rc<RefCell<Stream>>>,
+    // What each node has sent of a packet it hasn't finished
+    partial: Vec<Vec<Value>>,
     nat: Option<(Value, Value)>,
+    round: usize,
+    stats: Vec<NodeStats>,
+    capture: Option<Vec<Packet>>,
+    replay: Option<VecDeque<Packet>>,
 }
 
 impl Router {
-    fn new(stream: Rc<RefCell<Stream>>) -> Router {
+    fn new(outputs: Vec<Rc<RefCell<Stream>>>, record: bool) -> Router {
+        let stats = vec![NodeStats::default(); outputs.len()];
         Router {
-            stream,
+            partial: vec![Vec::new(); outputs.len()],
+            outputs,
             nat: None,
+            round: 0,
+            stats,
+            capture: if record { Some(Vec::new()) } else { None },
+            replay: None,
         }
     }
 
-    fn route(&mut self, comps: &[Computer]) -> bool {
-        let new_packets = self.stream.borrow_mut().read_all();
-        log::debug!("new packets: {}", new_packets.len() / 3);
-        if new_packets.is_empty() {
-            return true;
-        }
-        for packet in new_packets.chunks(3) {
-            let a = packet[0];
-            let x = packet[1];
-            let y = packet[2];
-            if a == 255 {
-                self.nat = Some((x, y));
-            } else {
-                comps[a as usize].input().unwrap().borrow_mut().write_all(&[x, y]);
+    fn route(&mut self, comps: &[Computer]) {
+        self.round += 1;
+        let mut packets = Vec::new();
+        for (source, (output, partial)) in self.outputs.iter().zip(self.partial.iter_mut()).enumerate() {
+            partial.extend(output.borrow_mut().read_all());
+            let whole = partial.len() - partial.len() % 3;
+            for packet in partial.drain(..whole).collect::<Vec<_>>().chunks_exact(3) {
+                packets.push(Packet { round: self.round, source: source as Value, destination: packet[0], x: packet[1], y: packet[2] });
+            }
+        }
+        let round = self.round;
+        if let Some(replay) = &mut self.replay {
+            let mut recorded = Vec::new();
+            while let Some(packet) = replay.front().filter(|it| it.round == round).copied() {
+                replay.pop_front();
+                // The NAT's packets are sent again by wake
+                if packet.source != NAT {
+                    recorded.push(packet);
+                }
+            }
+            if recorded != packets {
+                log::warn!("Round {} diverged from the capture: {} live packets, {} recorded", round, packets.len(), recorded.len());
+            }
+            packets = recorded;
+        }
+        log::debug!("new packets: {}", packets.len());
+
+        let mut active = vec![false; self.stats.len()];
+        for &packet in &packets {
+            if let Some(a) = active.get_mut(packet.source as usize) {
+                *a = true;
             }
+            if let Some(a) = active.get_mut(packet.destination as usize) {
+                *a = true;
+            }
+            self.deliver(packet, comps);
+        }
+        for (stats, _) in self.stats.iter_mut().zip(active).filter(|(_, a)| !a) {
+            stats.idle_rounds += 1;
+        }
+    }
+
+    // Sends the NAT's last packet to address 0 and returns its y value. A capture can leave the NAT
+    // without one.
+    fn wake(&mut self, comps: &[Computer]) -> Result<Value, Error> {
+        let (x, y) = self.nat.ok_or_else(|| stopped(Status::Idle))?;
+        self.deliver(Packet { round: self.round, source: NAT, destination: 0, x, y }, comps);
+        Ok(y)
+    }
+
+    fn deliver(&mut self, packet: Packet, comps: &[Computer]) {
+        if let Some(capture) = &mut self.capture {
+            capture.push(packet);
+        }
+        if let Some(stats) = self.stats.get_mut(packet.source as usize) {
+            stats.sent += 1;
         }
-        false
+        if packet.destination == NAT {
+            self.nat = Some((packet.x, packet.y));
+        } else if let (Some(stats), Some(comp)) = (self.stats.get_mut(packet.destination as usize), comps.get(packet.destination as usize)) {
+            stats.received += 1;
+            comp.input().unwrap().borrow_mut().write_all(&[packet.x, packet.y]);
+        } else {
+            log::warn!("Dropping packet {} for unknown address {}", packet, packet.destination);
+        }
+    }
+
+    fn summary(&self) -> String {
+        let mut s = format!("{} rounds\nnode  sent  received  idle\n", self.round);
+        for (node, stats) in self.stats.iter().enumerate() {
+            s.push_str(&format!("{:>4}  {:>4}  {:>8}  {:>4}\n", node, stats.sent, stats.received, stats.idle_rounds));
+        }
+        s
+    }
+
+    fn finish(&self, capture_name: &str, config: &Config) {
+        log::debug!("{}", self.summary());
+        if let Some(capture) = &self.capture {
+            let lines: Vec<String> = capture.iter().map(|it| it.to_string()).collect();
+            export::write(config, capture_name, lines.join("\n") + "\n");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn packet_format() {
+        let packet = Packet { round: 3, source: 255, destination: 0, x: 12, y: -4 };
+        assert_eq!("3 255 0 12 -4", packet.to_string());
+        assert_eq!(Ok(packet), packet.to_string().parse());
+        assert!("3 255 0 12".parse::<Packet>().is_err());
+        let capture = parse_capture(&["# comment".to_string(), "1 2 3 4 5".to_string(), "".to_string()]).unwrap();
+        assert_eq!(1, capture.len());
+        let e = parse_capture(&["1 2 3 4 5".to_string(), "2 255 50 1 1".to_string()]).err().unwrap();
+        assert_eq!("<input>:3: addresses go from 0 to 49 or are the NAT's 255\n  2 255 50 1 1", e.to_string());
+        assert!(parse_capture(&["1 -1 3 4 5".to_string()]).is_err());
+    }
+
+    #[test]
+    fn partial_packets() {
+        let output = Stream::new_wrapped();
+        let mut router = Router::new(vec![Rc::clone(&output)], false);
+        output.borrow_mut().write_all(&[NAT, 1]);
+        router.route(&[]);
+        assert_eq!(None, router.nat);
+        output.borrow_mut().write_all(&[2, NAT]);
+        router.route(&[]);
+        assert_eq!(Some((1, 2)), router.nat);
+        output.borrow_mut().write_all(&[3, 4]);
+        router.route(&[]);
+        assert_eq!(Some((3, 4)), router.nat);
+    }
+
+    #[test]
+    fn stops() {
+        let halts = vec!["99".to_string()];
+        assert_eq!(Err(Error::Unsolvable("All 50 nodes halted".to_string())), run1(halts.clone(), &Config::default()));
+        assert_eq!(Err(Error::Unsolvable("All 50 nodes halted".to_string())), run2(halts, &Config::default()));
+        // Reads forever without sending anything
+        let listens = vec!["3,7,1105,1,0,99,0,0".to_string()];
+        assert!(matches!(run1(listens.clone(), &Config::default()), Err(Error::Unsolvable(_))));
+        assert!(matches!(run2(listens, &Config::default()), Err(Error::Unsolvable(_))));
     }
 }
@@ -1,24 +1,39 @@
-use std::collections::{HashMap, HashSet};
-
-pub fn run1(input: Vec<String>) -> u64 {
-    let mut map = parse_map(&input);
-    let mut prevs = HashSet::new();
-    loop {
-        let bdr = calc_biodiversity(&map);
-        log::debug!("Biodiverstiy: {}", bdr);
-        if !prevs.insert(bdr) {
-            return bdr;
+use crate::config::Config;
+use crate::{cycle, gif};
+use crate::params::{Kind, Param};
+use crate::error::Error;
+use crate::grid::Grid;
+use std::collections::HashMap;
+
+solution!(24, 1, "Planet of Discord", "24.txt", run1, &[]);
+solution!(24, 2, "Planet of Discord", "24.txt", run2, &[MINUTES]);
+
+const MINUTES: Param = Param { name: "minutes", default: "200", kind: Kind::Count, help: "Minutes to let the recursive bugs spread" };
+
+pub fn run1(input: Vec<String>, config: &Config) -> Result<u64, Error> {
+    // The first layout to appear twice is the one the cycle starts at
+    let grid = parse_grid(&input)?;
+    let cycle = cycle::brent(grid.clone(), step1);
+    log::debug!("{:?}", cycle);
+    if gif::enabled(config) {
+        // Up to and including the layout that repeats
+        let mut animation = gif::Animation::for_grids(((0, 0), (4, 4)), 16, vec![[16, 16, 32], [120, 220, 60]], 25);
+        let mut current = grid.clone();
+        for _ in 0..=cycle.first_repeat() {
+            animation.push_grid(&current, |_, tile| (tile == Some(&Tile::Bug)) as u8);
+            current = step1(&current);
         }
-        step1(&mut map);
+        gif::save(config, &animation);
     }
+    Ok(calc_biodiversity(&cycle::fast_forward(grid, step1, cycle.tail)))
 }
 
-pub fn run2(input: Vec<String>) -> usize {
-    let mut map = parse_map(&input);
-    for _ in 0..200 {
+pub fn run2(input: Vec<String>, config: &Config) -> Result<usize, Error> {
+    let mut map = to_levels(&parse_grid(&input)?);
+    for _ in 0..config.params.get(&MINUTES)? {
         step2(&mut map);
     }
-    map.keys().count()
+    Ok(map.keys().count())
 }
 
 type Map = HashMap<(i32, i32, i32), Tile>;
@@ -29,42 +44,42 @@ enum Tile {
     Bug,
 }
 
-fn parse_map(input: &[String]) -> Map {
-    let mut map = HashMap::new();
-    for (y, row) in input.iter().enumerate() {
-        for (x, c) in row.chars().enumerate() {
-            let tile = match c {
-                '.' => Tile::Empty,
-                '#' => Tile::Bug,
-                _ => panic!("Unknown tile: {}", c)
-            };
-            map.insert((x as i32, y as i32, 0), tile);
-        }
+fn parse_grid(input: &[String]) -> Result<Grid<Tile>, Error> {
+    let grid = Grid::parse(&input.join("\n"), |c| match c {
+        '.' => Some(Tile::Empty),
+        '#' => Some(Tile::Bug),
+        _ => None
+    })?;
+    if grid.bounds() != Some(((0, 0), (4, 4))) {
+        return Err(Error::Input("Expected a 5 by 5 grid".to_string()))
     }
-    map
+    Ok(grid)
 }
 
-fn step1(map: &mut Map) {
-    let mut new_map = HashMap::new();
-    for y in 0..5 {
-        for x in 0..5 {
-            let coords = (x, y, 0);
-            let tile = map.get(&coords).copied().unwrap_or(Tile::Empty);
-            let adj_bugs = count_adj_bugs(&map, adj1, coords);
-            if tile == Tile::Bug && adj_bugs == 1 {
-                new_map.insert(coords, Tile::Bug);
-            } else if tile == Tile::Empty && (adj_bugs == 1 || adj_bugs == 2) {
-                new_map.insert(coords, Tile::Bug);
-            }
+// the recursive levels only track bugs, starting from level 0
+fn to_levels(grid: &Grid<Tile>) -> Map {
+    grid.iter()
+        .filter(|&(_, &tile)| tile == Tile::Bug)
+        .map(|((x, y), &tile)| ((x as i32, y as i32, 0), tile))
+        .collect()
+}
+
+fn step1(grid: &Grid<Tile>) -> Grid<Tile> {
+    grid.map(|pos, &tile| {
+        let adj_bugs = grid.neighbors4(pos).filter(|&(_, &it)| it == Tile::Bug).count();
+        if (tile == Tile::Bug && adj_bugs == 1) || (tile == Tile::Empty && (adj_bugs == 1 || adj_bugs == 2)) {
+            Tile::Bug
+        } else {
+            Tile::Empty
         }
-    }
-    *map = new_map;
+    })
 }
 
 fn step2(map: &mut Map) {
     let mut new_map = HashMap::new();
-    let min_level = map.keys().map(|&(_, _, l)| l).min().unwrap() - 1;
-    let max_level = map.keys().map(|&(_, _, l)| l).max().unwrap() + 1;
+    // With no bugs left only level 0 is looked at, which stays empty
+    let min_level = map.keys().map(|&(_, _, l)| l).min().unwrap_or(0) - 1;
+    let max_level = map.keys().map(|&(_, _, l)| l).max().unwrap_or(0) + 1;
     for level in min_level..=max_level {
         for y in 0..5 {
             for x in 0..5 {
@@ -73,10 +88,8 @@ fn step2(map: &mut Map) {
                 }
                 let coords = (x, y, level);
                 let tile = map.get(&coords).copied().unwrap_or(Tile::Empty);
-                let adj_bugs = count_adj_bugs(&map, adj2, coords);
-                if tile == Tile::Bug && adj_bugs == 1 {
-                    new_map.insert(coords, Tile::Bug);
-                } else if tile == Tile::Empty && (adj_bugs == 1 || adj_bugs == 2) {
+                let adj_bugs = count_adj_bugs(map, coords);
+                if (tile == Tile::Bug && adj_bugs == 1) || (tile == Tile::Empty && (adj_bugs == 1 || adj_bugs == 2)) {
                     new_map.insert(coords, Tile::Bug);
                 }
 
@@ -87,26 +100,14 @@ fn step2(map: &mut Map) {
 }
 
 
-fn count_adj_bugs(map: &Map, mut adj_fn: impl AdjFn, (x, y, level): (i32, i32, i32)) -> usize {
-    adj_fn(x, y, level).iter()
+fn count_adj_bugs(map: &Map, (x, y, level): (i32, i32, i32)) -> usize {
+    adj2(x, y, level).iter()
         .filter(|&it| {
             map.get(it).copied().unwrap_or(Tile::Empty) == Tile::Bug
         })
         .count()
 }
 
-trait AdjFn : FnMut(i32, i32, i32) -> Vec<(i32, i32, i32)> {}
-impl<T: FnMut(i32, i32, i32) -> Vec<(i32, i32, i32)>> AdjFn for T {}
-
-fn adj1(x: i32, y: i32, level: i32) -> Vec<(i32, i32, i32)> {
-    vec![
-        (x + 1, y, level),
-        (x, y + 1, level),
-        (x - 1, y, level),
-        (x, y - 1, level),
-    ]
-}
-
 fn adj2(x: i32, y: i32, level: i32) -> Vec<(i32, i32, i32)> {
     assert_ne!((2, 2), (x, y));
     let mut res = Vec::new();
@@ -153,16 +154,11 @@ fn adj2(x: i32, y: i32, level: i32) -> Vec<(i32, i32, i32)> {
     res
 }
 
-fn calc_biodiversity(map: &Map) -> u64 {
-    let mut res = 0;
-    for y in 0..5 {
-        for x in 0..5 {
-            if let Some(Tile::Bug) = map.get(&(x, y, 0)) {
-                res += 1 << (y*5 + x) as u64
-            }
-        }
-    }
-    res
+fn calc_biodiversity(grid: &Grid<Tile>) -> u64 {
+    grid.iter()
+        .filter(|&(_, &tile)| tile == Tile::Bug)
+        .map(|((x, y), _)| 1 << (y*5 + x) as u64)
+        .sum()
 }
 
 #[cfg(test)]
@@ -177,8 +173,16 @@ mod test {
 .....
 #....
 .#...";
-        let map = parse_map(&input.lines().map(|s| s.to_string()).collect_vec());
-        let bdr = calc_biodiversity(&map);
-        assert_eq!(2129920, bdr)
+        let grid = parse_grid(&input.lines().map(|s| s.to_string()).collect_vec()).unwrap();
+        let bdr = calc_biodiversity(&grid);
+        assert_eq!(2129920, bdr);
+        assert!(matches!(parse_grid(&[".....".to_string(), "..x..".to_string()]), Err(Error::Parse { line: 2, column: Some(3), .. })));
+        assert!(matches!(parse_grid(&[".....".to_string()]), Err(Error::Input(_))));
+    }
+
+    #[test]
+    fn no_bugs() {
+        let input = vec![".....".to_string(); 5];
+        assert_eq!(Ok(0), run2(input, &Config::default()));
     }
 }
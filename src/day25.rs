@@ -1,12 +1,19 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::interactive;
 use crate::intcode::*;
 use std::rc::Rc;
-use std::io::stdin;
 use std::collections::HashSet;
 use itertools::Itertools;
 use std::hash::Hash;
 
-pub fn run1(input: Vec<String>) {
-    let mut comp = Computer::new(parse_program(&input[0]));
+solution!(25, 1, "Cryostasis", "25.txt", run1, &[]);
+solution!(25, 2, "Cryostasis", "25.txt", run2);
+
+// Tries every combination of items on the pressure plate and returns the airlock password. When
+// interactive, shows each attempt and hands over to the keyboard if none of them work.
+pub fn run1(input: Vec<String>, config: &Config) -> Result<String, Error> {
+    let mut comp = Computer::new(read_program(&input)?);
     let in_st = Stream::new_wrapped();
     let out_st = Stream::new_wrapped();
     comp.set_input(Some(Rc::clone(&in_st)));
@@ -58,7 +65,7 @@ drop space heater
     in_st.borrow_mut().write_ascii(take_everything);
 
     comp.execute();
-    println!("{}", out_st.borrow_mut().read_ascii());
+    log::debug!("{}", out_st.borrow_mut().read_ascii());
 
     let ps = power_set(vec![
         "easter egg",
@@ -79,9 +86,16 @@ drop space heater
         s.push_str("south\n");
         in_st.borrow_mut().write_ascii(&s);
         comp.execute();
-        println!("{}", out_st.borrow_mut().read_ascii());
-        println!("{:?}", items);
-        prompt(&mut String::new()); // ignored
+        let text = out_st.borrow_mut().read_ascii();
+        if let Some(password) = find_password(&text) {
+            log::info!("Got through holding {:?}", items);
+            return Ok(password)
+        }
+        if interactive::enabled(config) {
+            println!("{}", text);
+            println!("{:?}", items);
+            interactive::prompt(""); // ignored
+        }
 
         s.clear();
         for item in &items {
@@ -91,22 +105,27 @@ drop space heater
         comp.execute();
     }
 
-    let mut buf = String::new();
+    if !interactive::enabled(config) {
+        return Err(Error::Unsolvable("No combination of items got through".to_string()))
+    }
     while let ComputerState::WaitingOnInput = comp.execute() {
         println!("{}", out_st.borrow_mut().read_ascii());
-        prompt(&mut buf);
-        in_st.borrow_mut().write_ascii(&buf);
+        in_st.borrow_mut().write_ascii(&interactive::prompt(""));
     }
-    println!("{}", out_st.borrow_mut().read_ascii());
+    let text = out_st.borrow_mut().read_ascii();
+    println!("{}", text);
+    Ok(find_password(&text).unwrap_or_default())
 }
 
-pub fn run2(_input: Vec<String>) -> &'static str {
-    "Merry Christmas!"
+// "... You should be able to get in by typing 1234 on the keypad ..."
+fn find_password(text: &str) -> Option<String> {
+    let rest = &text[text.find("typing ")? + "typing ".len()..];
+    let password: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    if password.is_empty() { None } else { Some(password) }
 }
 
-fn prompt(inp: &mut String) {
-    inp.clear();
-    stdin().read_line(inp).unwrap();
+pub fn run2(_input: Vec<String>) -> &'static str {
+    "Merry Christmas!"
 }
 
 fn power_set<T : Clone + Eq + Hash>(v: Vec<T>) -> Vec<HashSet<T>> {
@@ -123,6 +142,12 @@ fn power_set<T : Clone + Eq + Hash>(v: Vec<T>) -> Vec<HashSet<T>> {
 mod test {
     use super::*;
 
+    #[test]
+    fn password() {
+        assert_eq!(Some("2424308736".to_string()), find_password("Oh, hello! You should be able to get in by typing 2424308736 on the keypad at the main airlock."));
+        assert_eq!(None, find_password("Alert! Droids on this ship are heavier than the detected value!"));
+    }
+
     #[test]
     fn power_set_test() {
         let ps = power_set((0..4).collect());
@@ -1,7 +1,10 @@
+use crate::error::{self, ParseError};
 use std::str::FromStr;
-use std::num::ParseIntError;
 use itertools::Itertools;
 
+solution!(3, 1, "Crossed Wires", "3.txt", run1);
+solution!(3, 2, "Crossed Wires", "3.txt", run2);
+
 pub fn run1(input: Vec<Wire>) -> u32 {
     let av = to_lines(&input[0].0);
     log::debug!("av: {:?}", av);
@@ -22,7 +25,7 @@ pub fn run2(input: Vec<Wire>) -> u32 {
     av.iter()
         .cartesian_product(&bv)
         .filter_map(|(a, b)| find_intersection(a, b))
-        .map(|it| delay(it))
+        .map(delay)
         .min()
         .unwrap_or(0)
 }
@@ -38,16 +41,18 @@ pub enum Direction {
 }
 
 impl FromStr for Wire {
-    type Err = ParseIntError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let segments: Result<Vec<_>, _> = s.split(',').map(|it| it.parse()).collect();
-        Ok(Wire(segments?))
+        error::split_offsets(s, ",")
+            .map(|(offset, segment)| segment.parse().map_err(|e: ParseError| e.shift(offset)))
+            .collect::<Result<_, _>>()
+            .map(Wire)
     }
 }
 
 impl FromStr for Segment {
-    type Err = ParseIntError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let dir = match s.chars().next() {
@@ -55,9 +60,10 @@ impl FromStr for Segment {
             Some('U') => Direction::Up,
             Some('L') => Direction::Left,
             Some('D') => Direction::Down,
-            _ => unreachable!("lol")
+            Some(c) => return Err(ParseError::at(1, format!("expected a direction of R, U, L or D but got '{}'", c))),
+            None => return Err(ParseError::at(1, "expected a segment like R75")),
         };
-        let length = s[1..].parse()?;
+        let length = error::parse_number(&s[1..], 2)?;
         Ok(Segment { dir, length })
     }
 }
@@ -94,7 +100,7 @@ impl Line {
     }
 }
 
-fn to_lines(segments: &Vec<Segment>) -> Vec<Line> {
+fn to_lines(segments: &[Segment]) -> Vec<Line> {
     segments.iter()
         .scan(Line::new((0, 0), (0, 0)), |state, it| {
             let line = Line::from_segment(state, it);
@@ -174,13 +180,20 @@ mod test {
     fn pt1ex1() {
         let input = "R75,D30,R83,U83,L12,D49,R71,U7,L72
 U62,R66,U55,R34,D71,R55,D58,R83";
-        assert_eq!(159, run1(parse_input(input)));
+        assert_eq!(159, run1(parse_input(input).unwrap()));
     }
 
     #[test]
     fn pt2ex1() {
         let input = "R75,D30,R83,U83,L12,D49,R71,U7,L72
 U62,R66,U55,R34,D71,R55,D58,R83";
-        assert_eq!(610, run2(parse_input(input)));
+        assert_eq!(610, run2(parse_input(input).unwrap()));
+    }
+
+    #[test]
+    fn bad_segments() {
+        assert_eq!(Some(5), "R75,X30".parse::<Wire>().err().unwrap().column);
+        assert_eq!(Some(6), "R75,D3x".parse::<Wire>().err().unwrap().column);
+        assert_eq!(Some(5), "R75,".parse::<Wire>().err().unwrap().column);
     }
 }
@@ -1,9 +1,37 @@
-pub fn run1() -> String {
-    (134792..675811).filter(|&it| test(it)).count().to_string()
+use crate::error::{self, ParseError};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+solution!(4, 1, "Secure Container", "4.txt", run1);
+solution!(4, 2, "Secure Container", "4.txt", run2);
+
+pub fn run1(input: Vec<Bounds>) -> usize {
+    input[0].range().filter(|&it| test(it)).count()
+}
+
+pub fn run2(input: Vec<Bounds>) -> usize {
+    input[0].range().filter(|&it| test2(it)).count()
+}
+
+// The puzzle input, e.g. 134792-675811
+pub struct Bounds {
+    low: i32,
+    high: i32,
+}
+
+impl Bounds {
+    fn range(&self) -> RangeInclusive<i32> {
+        self.low..=self.high
+    }
 }
 
-pub fn run2() -> String {
-    (134792..675811).filter(|&it| test2(it)).count().to_string()
+impl FromStr for Bounds {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (low, high) = s.split_once('-').ok_or_else(|| ParseError::new(format!("expected low-high but got '{}'", s)))?;
+        Ok(Bounds { low: error::parse_number(low, 1)?, high: error::parse_number(high, low.len() + 2)? })
+    }
 }
 
 
@@ -16,7 +44,7 @@ fn test(x: i32) -> bool {
 
 fn test2(x: i32) -> bool {
     let padded: Vec<char> = format!(" {} ", x).chars().collect();
-    let only2 = (&padded[..]).windows(4).any(|w| {
+    let only2 = padded.windows(4).any(|w| {
         w[0] != w[1] && w[1] == w[2] && w[2] != w[3]
     });
     test(x) && only2
@@ -24,7 +52,7 @@ fn test2(x: i32) -> bool {
 
 #[cfg(test)]
 mod test {
-    use crate::day4::test2;
+    use super::*;
 
     #[test]
     fn pt2() {
@@ -32,4 +60,12 @@ mod test {
         assert!(test2(111122));
         assert!(!test2(123444));
     }
+
+    #[test]
+    fn bounds() {
+        let bounds: Bounds = "10-12".parse().unwrap();
+        assert_eq!(vec![10, 11, 12], bounds.range().collect::<Vec<_>>());
+        assert!("10".parse::<Bounds>().is_err());
+        assert_eq!(Some(4), "10-1x".parse::<Bounds>().err().unwrap().column);
+    }
 }
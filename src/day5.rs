@@ -1,12 +1,16 @@
+use crate::error::Error;
 use crate::intcode;
 
-pub fn run1(input: Vec<String>) -> Vec<i64> {
-    let mut program = intcode::parse_program(&input[0]);
-    intcode::execute(&mut program, &mut [1].iter())
+solution!(5, 1, "Sunny with a Chance of Asteroids", "5.txt", run1);
+solution!(5, 2, "Sunny with a Chance of Asteroids", "5.txt", run2);
+
+pub fn run1(input: Vec<String>) -> Result<Vec<i64>, Error> {
+    let mut program = intcode::read_program(&input)?;
+    Ok(intcode::execute(&mut program, &mut [1].iter()))
 }
 
-pub fn run2(input: Vec<String>) -> i64 {
-    let mut program = intcode::parse_program(&input[0]);
+pub fn run2(input: Vec<String>) -> Result<i64, Error> {
+    let mut program = intcode::read_program(&input)?;
     let output = intcode::execute(&mut program, &mut [5].iter());
-    output[0]
+    Ok(output[0])
 }
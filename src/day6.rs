@@ -1,6 +1,10 @@
+use crate::error::ParseError;
 use std::str::FromStr;
 use std::collections::{HashMap, HashSet};
 
+solution!(6, 1, "Universal Orbit Map", "6.txt", run1);
+solution!(6, 2, "Universal Orbit Map", "6.txt", run2);
+
 pub fn run1(input: Vec<Orbit>) -> u32 {
     let orbits = to_orbits(input);
     orbits
@@ -21,12 +25,17 @@ pub struct Orbit {
 }
 
 impl FromStr for Orbit {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.split(')').collect();
-        if parts.len() != 2 { return Err(()) }
-        Ok(Orbit { orbitee: parts[0].to_string(), orbiter: parts[1].to_string() })
+        let (orbitee, orbiter) = s.split_once(')').ok_or_else(|| ParseError::new("expected A)B"))?;
+        if orbitee.is_empty() {
+            return Err(ParseError::at(1, "expected a name before ')'"))
+        }
+        if orbiter.is_empty() || orbiter.contains(')') {
+            return Err(ParseError::at(orbitee.len() + 2, "expected a single name after ')'"))
+        }
+        Ok(Orbit { orbitee: orbitee.to_string(), orbiter: orbiter.to_string() })
     }
 }
 
@@ -39,7 +48,7 @@ fn count_orbits(orbits: &HashMap<String, String>, object: &str, target: &str) ->
         .enumerate()
         .find(|(_, it)| it == &target)
         .map(|(i, _)| (i + 1) as u32)
-        .expect(&format!("{} does not orbit {}", object, target))
+        .unwrap_or_else(|| panic!("{} does not orbit {}", object, target))
 }
 
 fn  nearest_common_parent<'a>(orbits: &'a HashMap<String, String>, a: &'a str, b: &'a str) -> &'a str {
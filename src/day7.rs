@@ -1,93 +1,76 @@
-use crate::intcode;
-use itertools::Itertools;
-use std::rc::Rc;
-use std::cell::RefCell;
-use crate::intcode::ComputerState;
+use crate::circuit::{Circuit, FEEDBACK, SERIES};
+use crate::config::Config;
+use crate::error::{self, Error, ParseError};
+use crate::intcode::{self, Value};
+use std::ops::Range;
 
-pub fn run1(input: Vec<String>) -> i64 {
-    let program = intcode::parse_program(&input[0]);
-    vec![0, 1, 2, 3, 4]
-        .into_iter()
-        .permutations(5)
-        .map(|phases| run_amps_once(&program, &phases[..]))
-        .max()
-        .unwrap()
-}
+solution!(7, 1, "Amplification Circuit", "7.txt", run1, &[]);
+solution!(7, 2, "Amplification Circuit", "7.txt", run2, &[]);
+
+const CIRCUIT: &str = "circuit:";
+const PHASES: &str = "phases:";
 
-pub fn run2(input: Vec<String>) -> i64 {
-    let program = intcode::parse_program(&input[0]);
-    vec![5, 6, 7, 8, 9]
-        .into_iter()
-        .permutations(5)
-        .map(|phases| run_amps_looped(&program, &phases[..]))
-        .max()
-        .unwrap()
+pub fn run1(input: Vec<String>, config: &Config) -> Result<i64, Error> {
+    max_signal(&input, SERIES, 0..5, config)
 }
 
-fn run_amps_once(program: &intcode::Program, phases: &[i32]) -> i64 {
-    let mut signal = 0;
-    for i in 0..5 {
-        let input = [phases[i] as i64, signal];
-        let output = intcode::execute(&mut program.clone(), &mut input.iter());
-        signal = output[0];
-    }
-    signal
+pub fn run2(input: Vec<String>, config: &Config) -> Result<i64, Error> {
+    max_signal(&input, FEEDBACK, 5..10, config)
 }
 
-fn run_amps_looped(program: &intcode::Program, phases: &[i32]) -> i64 {
-    let mut computers = vec![
-        intcode::Computer::new(program.clone()),
-        intcode::Computer::new(program.clone()),
-        intcode::Computer::new(program.clone()),
-        intcode::Computer::new(program.clone()),
-        intcode::Computer::new(program.clone()),
-    ];
+fn max_signal(input: &[String], circuit: &str, phases: Range<Value>, config: &Config) -> Result<i64, Error> {
+    let program = intcode::read_program(input)?;
+    let (circuit, phases) = read_setup(input, circuit, phases)?;
+    let (best, signal) = circuit.max_signal(&program, phases.clone(), config.jobs())
+        .ok_or_else(|| Error::Unsolvable(format!("No phases from {:?} get a signal out of the {} amps", phases, circuit.amps().len())))?;
+    log::info!("phases: {:?}", best);
+    Ok(signal)
+}
 
-    for i in 0..5 {
-        let mut s = intcode::Stream::new();
-        s.write(phases[i] as i64);
-        if i == 0 {
-            s.write(0);
+// Lines after the program like "circuit: in -> A -> B -> out" and "phases: 0..3" replace the puzzle's
+// circuit and the range of phases tried
+fn read_setup(input: &[String], circuit: &str, phases: Range<Value>) -> Result<(Circuit, Range<Value>), Error> {
+    let mut circuit = circuit.parse().map_err(Error::Input)?;
+    let mut phases = phases;
+    for (i, line) in input.iter().enumerate().skip(1).filter(|(_, line)| !line.trim().is_empty()) {
+        if let Some(rest) = line.strip_prefix(CIRCUIT) {
+            circuit = rest.parse().map_err(|e: String| ParseError::at(CIRCUIT.len() + 1, e).on_line(i + 1, line))?;
+        } else if let Some(rest) = line.strip_prefix(PHASES) {
+            phases = parse_range(rest).map_err(|e| e.shift(PHASES.len()).on_line(i + 1, line))?;
+        } else {
+            return Err(ParseError::at(1, format!("expected '{}' or '{}' after the program", CIRCUIT, PHASES)).on_line(i + 1, line))
         }
-        computers[i].set_input(Some(Rc::new(RefCell::new(s))));
     }
+    Ok((circuit, phases))
+}
 
-    for i in 0..5 {
-        let o = (i + 1) % 5;
-        let s = computers[o].input();
-        computers[i].set_output(s);
-    }
-
-    let mut running = 4;
-    let mut limit = 1000;
-    loop {
-        log::debug!("Starting execute of amp {}", running);
-        let state = computers[running].execute();
-        log::debug!("Amp {} suspended with state: {:?}", running, state);
-        if state == ComputerState::Halted && running == 4 {
-            break
-        }
-
-        running = (running + 4) % 5;
-
-        limit -= 1;
-        if limit == 0 {
-            panic!("Hit the limit")
-        }
+// "low..high", not including high
+fn parse_range(s: &str) -> Result<Range<Value>, ParseError> {
+    let pieces: Vec<(usize, &str)> = error::split_offsets(s, "..").collect();
+    if pieces.len() != 2 {
+        return Err(ParseError::at(1, "expected a range like 5..10"))
     }
-    computers[4].output().unwrap().borrow_mut().read().expect("Expect a final value")
+    let number = |(offset, piece): (usize, &str)| error::parse_number(piece.trim(), offset + piece.len() - piece.trim_start().len() + 1);
+    Ok(number(pieces[0])?..number(pieces[1])?)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    fn input(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|it| it.to_string()).collect()
+    }
+
     #[test]
-    fn ex1() {
-        let program = intcode::parse_program(
-            "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,
-             27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5");
-        let output = run_amps_looped(&program, &[9,8,7,6,5]);
-        assert_eq!(139629729, output);
+    fn setup() {
+        let program = "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0";
+        assert_eq!(Ok(43210), run1(input(&[program]), &Config::default()));
+        assert_eq!(Ok(4321), run1(input(&[program, "circuit: in -> A -> B -> C -> D -> out", "phases: 1..5"]), &Config::default()));
+        assert!(matches!(run1(input(&[program, "phases: 0..4"]), &Config::default()), Err(Error::Unsolvable(_))));
+        let e = run1(input(&[program, "phases: 0..x"]), &Config::default()).err().unwrap();
+        assert_eq!("<input>:2:12: bad number 'x': invalid digit found in string\n  phases: 0..x\n             ^", e.to_string());
+        assert!(matches!(run1(input(&[program, "circuit: in -> out"]), &Config::default()), Err(Error::Parse { line: 2, .. })));
+        assert!(matches!(run1(input(&[program, "amps: 3"]), &Config::default()), Err(Error::Parse { line: 2, column: Some(1), .. })));
     }
 }
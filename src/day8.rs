@@ -1,60 +1,171 @@
-pub fn run1(input: Vec<String>) -> u32 {
-    let image = Image::from_string_with_size(&input[0], 25, 6);
-    image.layers.iter()
-        .min_by_key(|it| num_of(it, 0))
-        .map(|layer| num_of(layer, 1) * num_of(layer, 2))
-        .unwrap()
+use crate::answer::Answer;
+use crate::config::Config;
+use crate::error::Error;
+use crate::{export, ocr, png, pnm};
+use crate::params::{Kind, Param};
+
+solution!(8, 1, "Space Image Format", "8.txt", run1, &[WIDTH, HEIGHT]);
+solution!(8, 2, "Space Image Format", "8.txt", run2, &[WIDTH, HEIGHT]);
+
+const WIDTH: Param = Param { name: "width", default: "25", kind: Kind::Positive, help: "Width of the image in pixels" };
+const HEIGHT: Param = Param { name: "height", default: "6", kind: Kind::Positive, help: "Height of the image in pixels" };
+
+const BLACK: u8 = 0;
+const WHITE: u8 = 1;
+const TRANSPARENT: u8 = 2;
+
+pub fn run1(input: Vec<String>, config: &Config) -> Result<u32, Error> {
+    let image = read_image(&input, config)?;
+    Ok(image.layer_stats().iter()
+        .min_by_key(|it| it.count(0))
+        .map(|stats| (stats.count(1) * stats.count(2)) as u32)
+        .unwrap())
 }
 
-pub fn run2(input: Vec<String>) {
-    let image = Image::from_string_with_size(&input[0], 25, 6);
-    image.print();
+pub fn run2(input: Vec<String>, config: &Config) -> Result<Answer, Error> {
+    let image = read_image(&input, config)?;
+    log::debug!("image:\n{}", image.render());
+    export::write(config, "8-2.pbm", image.to_pbm());
+    export::write(config, "8-2.png", image.to_png());
+    let flattened = Image::from_layers(vec![image.composite()], image.width, image.height).unwrap();
+    export::write(config, "8-2.sif", flattened.encode());
+    let rows: Vec<Vec<bool>> = image.composite().into_iter()
+        .map(|row| row.into_iter().map(|it| it == WHITE).collect())
+        .collect();
+    Ok(ocr::answer(rows))
 }
 
-type Layer = Vec<Vec<u32>>;
+fn read_image(input: &[String], config: &Config) -> Result<Image, Error> {
+    let line = input.first().map_or("", String::as_str);
+    Image::decode(line, config.params.get(&WIDTH)?, config.params.get(&HEIGHT)?).map_err(Error::Input)
+}
+
+type Layer = Vec<Vec<u8>>;
 struct Image {
     layers: Vec<Layer>,
     width: usize,
     height: usize,
 }
 
+struct LayerStats {
+    counts: [usize; 10],
+}
+
+impl LayerStats {
+    fn count(&self, digit: u8) -> usize {
+        self.counts[digit as usize]
+    }
+}
+
 impl Image {
-    fn from_string_with_size(s: &str, width: usize, height: usize) -> Image {
-        let mut nums = s.chars().map(|it| it.to_digit(10).unwrap());
-        let mut layers = Vec::new();
-        'outer: loop {
-            let mut layer = Vec::new();
-            for _y in 0..height {
-                let mut row = Vec::new();
-                for _x in 0..width {
-                    match nums.next() {
-                        Some(n) => row.push(n),
-                        None => break 'outer
-                    }
-                }
-                layer.push(row)
+    fn decode(s: &str, width: usize, height: usize) -> Result<Image, String> {
+        if width == 0 || height == 0 {
+            return Err(format!("Invalid image size {}x{}", width, height))
+        }
+        let nums: Vec<u8> = s.trim().chars()
+            .enumerate()
+            .map(|(i, c)| c.to_digit(10)
+                .map(|d| d as u8)
+                .ok_or_else(|| format!("Invalid pixel '{}' at position {}", c, i)))
+            .collect::<Result<_, _>>()?;
+        let layer_size = width * height;
+        if nums.is_empty() || !nums.len().is_multiple_of(layer_size) {
+            return Err(format!("{} pixels is not a whole number of {}x{} layers", nums.len(), width, height))
+        }
+        let layers = nums.chunks(layer_size)
+            .map(|layer| layer.chunks(width).map(|row| row.to_vec()).collect())
+            .collect();
+        Ok(Image { layers, width, height })
+    }
+
+    fn from_layers(layers: Vec<Layer>, width: usize, height: usize) -> Result<Image, String> {
+        for (i, layer) in layers.iter().enumerate() {
+            if layer.len() != height || layer.iter().any(|row| row.len() != width) {
+                return Err(format!("Layer {} is not {}x{}", i, width, height))
+            }
+            if layer.iter().flatten().any(|&it| it > 9) {
+                return Err(format!("Layer {} has a pixel that is not a digit", i))
             }
-            layers.push(layer)
         }
-        Image { layers, width, height }
+        Ok(Image { layers, width, height })
     }
 
-    fn pixel_at(&self, x: usize, y: usize) -> u32 {
-        self.layers.iter().map(|it| it[y][x]).find(|it| *it != 2).unwrap_or(2)
+    fn encode(&self) -> String {
+        self.layers.iter()
+            .flatten()
+            .flatten()
+            .map(|&d| char::from(b'0' + d))
+            .collect()
     }
 
-    fn print(&self) {
-        for y in 0..self.height {
-            let mut s = String::new();
-            for x in 0..self.width {
-                let pixel = self.pixel_at(x, y);
-                s.push(if pixel == 0 { ' ' } else { '#' });
-            }
-            println!("{}", s);
+    fn layer_stats(&self) -> Vec<LayerStats> {
+        self.layers.iter()
+            .map(|layer| {
+                let mut counts = [0; 10];
+                layer.iter().flatten().for_each(|&d| counts[d as usize] += 1);
+                LayerStats { counts }
+            })
+            .collect()
+    }
+
+    fn pixel_at(&self, x: usize, y: usize) -> u8 {
+        self.layers.iter().map(|it| it[y][x]).find(|it| *it != TRANSPARENT).unwrap_or(TRANSPARENT)
+    }
+
+    fn composite(&self) -> Layer {
+        (0..self.height)
+            .map(|y| (0..self.width).map(|x| self.pixel_at(x, y)).collect())
+            .collect()
+    }
+
+    fn render(&self) -> String {
+        let mut s = String::new();
+        for row in self.composite() {
+            s.extend(row.into_iter().map(|pixel| if pixel == WHITE { '#' } else { ' ' }));
+            s.push('\n');
         }
+        s
+    }
+
+    fn to_pbm(&self) -> String {
+        pnm::pbm(self.width, self.height, |x, y| self.pixel_at(x, y) == BLACK)
+    }
+
+    // Pixels that are transparent all the way down come out gray
+    fn to_png(&self) -> Vec<u8> {
+        png::grayscale(self.width, self.height, |x, y| match self.pixel_at(x, y) {
+            BLACK => 0,
+            WHITE => 255,
+            _ => 128,
+        })
     }
 }
 
-fn num_of(layer: &Layer, digit: u32) -> u32 {
-    layer.iter().flat_map(|it| it.iter()).filter(|d| **d == digit).count() as u32
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn layers() {
+        let image = Image::decode("123456789012", 3, 2).unwrap();
+        assert_eq!(vec![vec![vec![1, 2, 3], vec![4, 5, 6]], vec![vec![7, 8, 9], vec![0, 1, 2]]], image.layers);
+        assert_eq!(1, image.layer_stats()[1].count(0));
+        assert_eq!("123456789012", image.encode());
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Image::decode("123456789012", 0, 2).is_err());
+        assert!(Image::decode("1234567890123", 3, 2).is_err());
+        assert!(Image::decode("12345678901x", 3, 2).is_err());
+        assert!(Image::from_layers(vec![vec![vec![1, 2]]], 3, 1).is_err());
+    }
+
+    #[test]
+    fn pt2ex1() {
+        let image = Image::decode("0222112222120000", 2, 2).unwrap();
+        assert_eq!(vec![vec![0, 1], vec![1, 0]], image.composite());
+        let flat = Image::from_layers(vec![image.composite()], 2, 2).unwrap();
+        assert_eq!("0110", flat.encode());
+    }
 }
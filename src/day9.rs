@@ -1,11 +1,15 @@
+use crate::error::Error;
 use crate::intcode;
 
-pub fn run1(input: Vec<String>) -> Vec<i64> {
-    let mut program = intcode::parse_program(&input[0]);
-    intcode::execute(&mut program, &mut [1].iter())
+solution!(9, 1, "Sensor Boost", "9.txt", run1);
+solution!(9, 2, "Sensor Boost", "9.txt", run2);
+
+pub fn run1(input: Vec<String>) -> Result<Vec<i64>, Error> {
+    let mut program = intcode::read_program(&input)?;
+    Ok(intcode::execute(&mut program, &mut [1].iter()))
 }
 
-pub fn run2(input: Vec<String>) -> Vec<i64> {
-    let mut program = intcode::parse_program(&input[0]);
-    intcode::execute(&mut program, &mut [2].iter())
+pub fn run2(input: Vec<String>) -> Result<Vec<i64>, Error> {
+    let mut program = intcode::read_program(&input)?;
+    Ok(intcode::execute(&mut program, &mut [2].iter()))
 }
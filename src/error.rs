@@ -0,0 +1,144 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::num::ParseIntError;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    // The input couldn't be read at all
+    Input(String),
+    // The input was read but the puzzle can't be solved with it
+    Unsolvable(String),
+    Parse {
+        file: Option<String>,
+        line: usize,
+        column: Option<usize>,
+        text: String,
+        reason: String,
+    },
+}
+
+impl Error {
+    pub fn in_file(self, name: &str) -> Error {
+        match self {
+            Error::Parse { line, column, text, reason, .. } =>
+                Error::Parse { file: Some(name.to_string()), line, column, text, reason },
+            e => e,
+        }
+    }
+}
+
+// input/6.txt:3:5: expected a name after ')'
+//   COM)
+//      ^
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Input(reason) | Error::Unsolvable(reason) => write!(f, "{}", reason),
+            Error::Parse { file, line, column, text, reason } => {
+                write!(f, "{}:{}", file.as_deref().unwrap_or("<input>"), line)?;
+                if let Some(column) = column {
+                    write!(f, ":{}", column)?;
+                }
+                write!(f, ": {}", reason)?;
+                // Grids built from rows have no line of text to point into
+                if !text.is_empty() {
+                    write!(f, "\n  {}", text)?;
+                    if let Some(column) = column {
+                        write!(f, "\n  {}^", " ".repeat(column - 1))?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// What FromStr impls return for a line of input. Columns count from 1.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    pub column: Option<usize>,
+    pub reason: String,
+}
+
+impl ParseError {
+    pub fn new(reason: impl Into<String>) -> ParseError {
+        ParseError { column: None, reason: reason.into() }
+    }
+
+    pub fn at(column: usize, reason: impl Into<String>) -> ParseError {
+        ParseError { column: Some(column), reason: reason.into() }
+    }
+
+    // For an error in a piece of the line that starts at offset
+    pub fn shift(self, offset: usize) -> ParseError {
+        ParseError { column: Some(self.column.unwrap_or(1) + offset), reason: self.reason }
+    }
+
+    // Lines count from 1
+    pub fn on_line(self, line: usize, text: &str) -> Error {
+        Error::Parse { file: None, line, column: self.column, text: text.to_string(), reason: self.reason }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.column {
+            Some(column) => write!(f, "column {}: {}", column, self.reason),
+            None => write!(f, "{}", self.reason),
+        }
+    }
+}
+
+impl From<String> for ParseError {
+    fn from(reason: String) -> ParseError {
+        ParseError::new(reason)
+    }
+}
+
+impl From<ParseIntError> for ParseError {
+    fn from(e: ParseIntError) -> ParseError {
+        ParseError::new(e.to_string())
+    }
+}
+
+impl From<Infallible> for ParseError {
+    fn from(e: Infallible) -> ParseError {
+        match e {}
+    }
+}
+
+// Parses a number that starts at column, pointing at it if it's bad
+pub fn parse_number<T>(s: &str, column: usize) -> Result<T, ParseError> where T: std::str::FromStr<Err = ParseIntError> {
+    s.parse().map_err(|e| ParseError::at(column, format!("bad number '{}': {}", s, e)))
+}
+
+// Like split, but with the byte offset each piece starts at
+pub fn split_offsets<'a>(s: &'a str, sep: &'a str) -> impl Iterator<Item = (usize, &'a str)> {
+    s.split(sep).scan(0, move |offset, piece| {
+        let start = *offset;
+        *offset += piece.len() + sep.len();
+        Some((start, piece))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_input;
+
+    #[test]
+    fn lines() {
+        assert_eq!(Ok(vec![1, 2]), parse_input::<u32>("1\n2"));
+        let e = parse_input::<u32>("1\nx").err().unwrap().in_file("1.txt");
+        assert_eq!("1.txt:2: invalid digit found in string\n  x", e.to_string());
+    }
+
+    #[test]
+    fn columns() {
+        let e = Error::Parse { file: None, line: 3, column: Some(5), text: "COM)".to_string(), reason: "expected a name after ')'".to_string() };
+        assert_eq!("<input>:3:5: expected a name after ')'\n  COM)\n      ^", e.to_string());
+        assert_eq!(Some(7), ParseError::at(2, "").shift(5).column);
+        assert_eq!(Some(4), parse_number::<i32>("1x", 4).err().unwrap().column);
+        assert_eq!(vec![(0, "a"), (3, "bc"), (7, "")], split_offsets("a, bc, ", ", ").collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,19 @@
+use crate::config::Config;
+use std::fs;
+
+pub fn enabled(config: &Config) -> bool {
+    config.export_dir.is_some()
+}
+
+// Writes the file into the Config's export directory. Does nothing when there isn't one.
+pub fn write(config: &Config, file_name: &str, contents: impl AsRef<[u8]>) {
+    let dir = match &config.export_dir {
+        Some(dir) => dir,
+        None => return
+    };
+    let path = dir.join(file_name);
+    match fs::create_dir_all(dir).and_then(|_| fs::write(&path, contents)) {
+        Ok(()) => log::info!("Exported {}", path.display()),
+        Err(e) => log::warn!("Could not export {}: {}", path.display(), e),
+    }
+}
@@ -0,0 +1,406 @@
+use crate::conformance;
+use crate::intcode::{Engine, Program, Run, Value};
+
+const DATA_CELLS: usize = 8;
+const MAX_STEPS: usize = 100_000;
+
+// Small xorshift generator so runs are reproducible from a seed without any dependencies
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // xorshift gets stuck on 0
+        Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn range(&mut self, from: Value, to: Value) -> Value {
+        from + (self.next() % (to - from) as u64) as Value
+    }
+}
+
+// Parameters refer to data cells rather than addresses, so programs stay well formed however they
+// are assembled
+#[derive(Copy, Clone, Debug)]
+enum Param {
+    Position(usize),
+    Immediate(Value),
+    Relative(usize),
+}
+
+#[derive(Clone, Debug)]
+enum Op {
+    // add, multiply, less than or equals
+    Arith(Value, Param, Param, Param),
+    Input(Param),
+    Output(Param),
+    AdjustBase(Value),
+    // Runs the body count times
+    Loop(Value, Vec<Op>),
+    // Jumps over the body if the condition is true (or false)
+    Skip(bool, Param, Vec<Op>),
+}
+
+#[derive(Clone, Debug)]
+struct Generated {
+    ops: Vec<Op>,
+    data: Vec<Value>,
+    input: Vec<Value>,
+}
+
+fn random_param(rng: &mut Rng, writable: bool) -> Param {
+    match rng.below(if writable { 2 } else { 3 }) {
+        0 => Param::Position(rng.below(DATA_CELLS)),
+        1 => Param::Relative(rng.below(DATA_CELLS)),
+        _ => Param::Immediate(rng.range(-10, 10)),
+    }
+}
+
+// The relative base only changes at the top level, so it is known wherever a parameter is assembled
+fn random_ops(rng: &mut Rng, len: usize, depth: usize) -> Vec<Op> {
+    (0..len)
+        .map(|_| match rng.below(10) {
+            0..=3 => {
+                let opcode = [1, 2, 7, 8][rng.below(4)];
+                Op::Arith(opcode, random_param(rng, false), random_param(rng, false), random_param(rng, true))
+            }
+            4 => Op::Input(random_param(rng, true)),
+            5 | 6 => Op::Output(random_param(rng, false)),
+            7 if depth == 0 => Op::AdjustBase(rng.range(-20, 20)),
+            8 if depth < 2 => {
+                let count = rng.range(1, 4);
+                let len = 1 + rng.below(4);
+                Op::Loop(count, random_ops(rng, len, depth + 1))
+            }
+            9 if depth < 2 => {
+                let if_true = rng.below(2) == 0;
+                let cond = random_param(rng, false);
+                let len = 1 + rng.below(3);
+                Op::Skip(if_true, cond, random_ops(rng, len, depth + 1))
+            }
+            _ => Op::Output(random_param(rng, false)),
+        })
+        .collect()
+}
+
+fn generate(rng: &mut Rng) -> Generated {
+    let len = 1 + rng.below(20);
+    let ops = random_ops(rng, len, 0);
+    let data = (0..DATA_CELLS).map(|_| rng.range(-10, 10)).collect();
+    let input = (0..rng.below(10)).map(|_| rng.range(-100, 100)).collect();
+    Generated { ops, data, input }
+}
+
+// Lays out the code, a halt, the data cells and then one counter per loop
+struct Assembler {
+    code: Vec<Value>,
+    // Positions in the code that need a data cell or counter address, filled in at the end
+    cell_refs: Vec<(usize, usize)>,
+    counter_refs: Vec<(usize, usize)>,
+    counters: usize,
+    rel_base: Value,
+}
+
+impl Assembler {
+    fn param(&mut self, param: Param) -> Value {
+        match param {
+            Param::Position(cell) => {
+                self.cell_refs.push((self.code.len(), cell));
+                0
+            }
+            Param::Immediate(v) => v,
+            Param::Relative(cell) => {
+                // Patched to the cell's address minus the relative base at this point
+                self.cell_refs.push((self.code.len(), cell));
+                -self.rel_base
+            }
+        }
+    }
+
+    fn mode(param: Param) -> Value {
+        match param {
+            Param::Position(_) => 0,
+            Param::Immediate(_) => 1,
+            Param::Relative(_) => 2,
+        }
+    }
+
+    fn push_instr(&mut self, opcode: Value, params: &[Param]) {
+        let modes: Value = params.iter().enumerate().map(|(i, &p)| Assembler::mode(p) * 10i64.pow(i as u32 + 2)).sum();
+        self.code.push(opcode + modes);
+        for &p in params {
+            let v = self.param(p);
+            self.code.push(v);
+        }
+    }
+
+    fn push_counter(&mut self, counter: usize) {
+        self.counter_refs.push((self.code.len(), counter));
+        self.code.push(0);
+    }
+
+    fn ops(&mut self, ops: &[Op]) {
+        for op in ops {
+            match op {
+                &Op::Arith(opcode, a, b, dest) => self.push_instr(opcode, &[a, b, dest]),
+                &Op::Input(dest) => self.push_instr(3, &[dest]),
+                &Op::Output(src) => self.push_instr(4, &[src]),
+                &Op::AdjustBase(d) => {
+                    self.push_instr(9, &[Param::Immediate(d)]);
+                    self.rel_base += d;
+                }
+                Op::Loop(count, body) => {
+                    let counter = self.counters;
+                    self.counters += 1;
+                    self.code.extend_from_slice(&[1101, *count, 0]);
+                    self.push_counter(counter);
+                    let start = self.code.len() as Value;
+                    self.ops(body);
+                    self.code.push(1001);
+                    self.push_counter(counter);
+                    self.code.push(-1);
+                    self.push_counter(counter);
+                    self.code.push(1005);
+                    self.push_counter(counter);
+                    self.code.push(start);
+                }
+                Op::Skip(if_true, cond, body) => {
+                    self.push_instr(if *if_true { 1005 } else { 1006 }, &[*cond]);
+                    let target = self.code.len();
+                    self.code.push(0);
+                    self.ops(body);
+                    self.code[target] = self.code.len() as Value;
+                }
+            }
+        }
+    }
+}
+
+fn assemble(generated: &Generated) -> Program {
+    let mut asm = Assembler { code: Vec::new(), cell_refs: Vec::new(), counter_refs: Vec::new(), counters: 0, rel_base: 0 };
+    asm.ops(&generated.ops);
+    asm.code.push(99);
+    let data_start = asm.code.len();
+    let counters_start = data_start + generated.data.len();
+    for &(at, cell) in &asm.cell_refs {
+        asm.code[at] += (data_start + cell) as Value;
+    }
+    for &(at, counter) in &asm.counter_refs {
+        asm.code[at] = (counters_start + counter) as Value;
+    }
+    asm.code.extend_from_slice(&generated.data);
+    asm.code.resize(counters_start + asm.counters, 0);
+    asm.code
+}
+
+// Engines may grow memory differently, so trailing zeros don't count. Panics only have to agree on
+// having happened.
+fn agree(a: &Result<Run, String>, b: &Result<Run, String>) -> bool {
+    fn trimmed(memory: &[Value]) -> &[Value] {
+        let len = memory.iter().rposition(|&it| it != 0).map_or(0, |it| it + 1);
+        &memory[..len]
+    }
+    match (a, b) {
+        (Ok(a), Ok(b)) => a.state == b.state && a.output == b.output && trimmed(&a.memory) == trimmed(&b.memory),
+        (Err(_), Err(_)) => true,
+        _ => false,
+    }
+}
+
+fn diverges(engines: &[&dyn Engine], program: &Program, input: &[Value]) -> bool {
+    let outcomes: Vec<_> = engines.iter().map(|it| conformance::run_caught(*it, program, input, MAX_STEPS)).collect();
+    outcomes.windows(2).any(|pair| !agree(&pair[0], &pair[1]))
+}
+
+// Every way of making the program a little simpler
+fn simplifications(generated: &Generated) -> Vec<Generated> {
+    let mut res: Vec<Generated> = shrink_ops(&generated.ops).into_iter()
+        .map(|ops| Generated { ops, ..generated.clone() })
+        .collect();
+    if !generated.input.is_empty() {
+        let mut input = generated.input.clone();
+        input.pop();
+        res.push(Generated { input, ..generated.clone() });
+    }
+    for (i, &v) in generated.data.iter().enumerate().filter(|(_, &v)| v != 0) {
+        let mut data = generated.data.clone();
+        data[i] = v / 2;
+        res.push(Generated { data, ..generated.clone() });
+    }
+    res
+}
+
+fn shrink_ops(ops: &[Op]) -> Vec<Vec<Op>> {
+    let mut res = Vec::new();
+    for (i, op) in ops.iter().enumerate() {
+        let replace = |with: Vec<Op>| [&ops[..i], &with, &ops[i + 1..]].concat();
+        res.push(replace(Vec::new()));
+        match op {
+            Op::Loop(count, body) => {
+                res.push(replace(body.clone()));
+                if *count > 1 {
+                    res.push(replace(vec![Op::Loop(1, body.clone())]));
+                }
+                res.extend(shrink_ops(body).into_iter().map(|body| replace(vec![Op::Loop(*count, body)])));
+            }
+            Op::Skip(if_true, cond, body) => {
+                res.push(replace(body.clone()));
+                res.extend(shrink_ops(body).into_iter().map(|body| replace(vec![Op::Skip(*if_true, *cond, body)])));
+            }
+            _ => {}
+        }
+    }
+    res
+}
+
+fn minimize(engines: &[&dyn Engine], mut generated: Generated) -> Generated {
+    while let Some(smaller) = simplifications(&generated).into_iter()
+        .find(|it| diverges(engines, &assemble(it), &it.input)) {
+        generated = smaller;
+    }
+    generated
+}
+
+pub struct Divergence {
+    pub seed: u64,
+    pub program: Program,
+    pub input: Vec<Value>,
+    pub outcomes: Vec<(String, Result<Run, String>)>,
+}
+
+// Written like a conformance case so it can be dropped into the suite once the right answer is known
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let join = |values: &[Value]| values.iter().map(|it| it.to_string()).collect::<Vec<_>>().join(",");
+        writeln!(f, "name: fuzz seed {}", self.seed)?;
+        writeln!(f, "program: {}", join(&self.program))?;
+        if !self.input.is_empty() {
+            writeln!(f, "input: {}", join(&self.input))?;
+        }
+        for (engine, outcome) in &self.outcomes {
+            match outcome {
+                Ok(run) => {
+                    let state = run.state.map_or("out of steps".to_string(), |it| format!("{:?}", it));
+                    writeln!(f, "# {}: {}, output [{}], memory [{}]", engine, state, join(&run.output), join(&run.memory))?
+                }
+                Err(e) => writeln!(f, "# {}: {}", engine, e)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// Tries programs generated from seed, seed + 1, ... and returns the first divergence, minimized
+pub fn fuzz(engines: &[&dyn Engine], seed: u64, count: u64) -> Option<Divergence> {
+    (seed..seed + count).find_map(|seed| {
+        let generated = generate(&mut Rng::new(seed));
+        if !diverges(engines, &assemble(&generated), &generated.input) {
+            return None
+        }
+        let minimal = minimize(engines, generated);
+        let program = assemble(&minimal);
+        let outcomes = engines.iter()
+            .map(|it| (it.name().to_string(), conformance::run_caught(*it, &program, &minimal.input, MAX_STEPS)))
+            .collect();
+        Some(Divergence { seed, program, input: minimal.input, outcomes })
+    })
+}
+
+// Ok if the engines agreed on every program, Err if they diverged or there weren't two to compare
+pub fn report(engines: &[&dyn Engine], seed: u64, count: u64) -> Result<String, String> {
+    if engines.len() < 2 {
+        return Err("Fuzzing needs at least two engines to compare".to_string())
+    }
+    let names: Vec<&str> = engines.iter().map(|it| it.name()).collect();
+    match fuzz(engines, seed, count) {
+        Some(divergence) => Err(format!("Divergence between {}:\n{}", names.join(", "), divergence)),
+        None => Ok(format!("{} programs from seed {}, no divergence between {}", count, seed, names.join(", "))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::{ComputerState, Interpreter, Stepper};
+
+    #[test]
+    fn generated_programs_terminate() {
+        for seed in 0..200 {
+            let generated = generate(&mut Rng::new(seed));
+            // Overflowing is fine, looping forever isn't
+            if let Ok(run) = conformance::run_caught(&Interpreter, &assemble(&generated), &generated.input, MAX_STEPS) {
+                assert!(run.state.is_some(), "seed {} ran out of steps", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn loops_and_relative_params() {
+        let generated = Generated {
+            ops: vec![
+                Op::AdjustBase(-7),
+                Op::Loop(3, vec![Op::Arith(1, Param::Relative(0), Param::Immediate(2), Param::Relative(0))]),
+                Op::Skip(true, Param::Position(0), vec![Op::Output(Param::Immediate(-1))]),
+                Op::Output(Param::Position(0)),
+            ],
+            data: vec![1],
+            input: vec![],
+        };
+        let run = Interpreter.run(&assemble(&generated), &[], MAX_STEPS);
+        assert_eq!(Some(ComputerState::Halted), run.state);
+        assert_eq!(vec![7], run.output);
+    }
+
+    // Adds instead of multiplying. Generated code is a contiguous run of instructions, so it can be
+    // decoded by sweeping up to the first halt.
+    struct Mutant;
+
+    impl Engine for Mutant {
+        fn name(&self) -> &str {
+            "mutant"
+        }
+
+        fn run(&self, program: &Program, input: &[Value], max_steps: usize) -> Run {
+            let mut program = program.clone();
+            let mut ip = 0;
+            while program[ip] != 99 {
+                let op = program[ip] % 100;
+                if op == 2 {
+                    program[ip] -= 1;
+                }
+                ip += match op {
+                    1 | 2 | 7 | 8 => 4,
+                    5 | 6 => 3,
+                    _ => 2,
+                };
+            }
+            Interpreter.run(&program, input, max_steps)
+        }
+    }
+
+    #[test]
+    fn finds_and_minimizes() {
+        assert!(fuzz(&[&Interpreter, &Stepper], 1, 50).is_none());
+        let divergence = fuzz(&[&Interpreter, &Mutant], 1, 50).expect("Expected the mutant to be caught");
+        // A multiply, something to observe it, the halt and data
+        assert!(divergence.program.len() < 24, "not minimized: {}", divergence);
+        assert_eq!(divergence.to_string(), fuzz(&[&Interpreter, &Mutant], divergence.seed, 1).unwrap().to_string());
+        assert!(report(&[&Interpreter, &Stepper], 1, 50).is_ok());
+        assert!(report(&[&Interpreter, &Mutant], 1, 50).unwrap_err().starts_with("Divergence"));
+    }
+
+    #[test]
+    fn needs_two_engines() {
+        assert_eq!(Err("Fuzzing needs at least two engines to compare".to_string()), report(&[&Interpreter], 0, 10));
+    }
+}
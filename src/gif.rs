@@ -0,0 +1,275 @@
+use crate::config::Config;
+use crate::grid::{Grid, Pos};
+use std::collections::HashMap;
+use std::fs;
+
+// Animated, palette based GIFs. A puzzle builds up its frames and saves them to the Config's gif path.
+pub type Rgb = [u8; 3];
+
+pub fn enabled(config: &Config) -> bool {
+    config.gif.is_some()
+}
+
+// Does nothing when there's no gif path
+pub fn save(config: &Config, animation: &Animation) {
+    let path = match &config.gif {
+        Some(path) => path,
+        None => return
+    };
+    match fs::write(path, animation.encode()) {
+        Ok(()) => log::info!("Saved {} frames to {}", animation.len(), path.display()),
+        Err(e) => log::warn!("Could not save {}: {}", path.display(), e),
+    }
+}
+
+pub struct Animation {
+    width: usize,
+    height: usize,
+    palette: Vec<Rgb>,
+    // Hundredths of a second between frames
+    delay: u16,
+    // Where grids are drawn from and how many pixels square each cell is
+    origin: Pos,
+    scale: usize,
+    // Already compressed, since there can be thousands
+    frames: Vec<Vec<u8>>,
+}
+
+impl Animation {
+    pub fn new(width: usize, height: usize, palette: Vec<Rgb>, delay: u16) -> Animation {
+        assert!(width <= 0xffff && height <= 0xffff, "GIFs are at most 65535 pixels wide and tall");
+        assert!(!palette.is_empty() && palette.len() <= 256, "GIFs need 1 to 256 colours");
+        Animation { width, height, palette, delay, origin: (0, 0), scale: 1, frames: Vec::new() }
+    }
+
+    // For frames drawn from grids, covering the cells from min to max
+    pub fn for_grids((min, max): (Pos, Pos), scale: usize, palette: Vec<Rgb>, delay: u16) -> Animation {
+        let width = (max.0 - min.0 + 1) as usize * scale;
+        let height = (max.1 - min.1 + 1) as usize * scale;
+        Animation { origin: min, scale, ..Animation::new(width, height, palette, delay) }
+    }
+
+    // One palette index per pixel, row by row
+    pub fn push(&mut self, pixels: Vec<u8>) {
+        assert_eq!(self.width * self.height, pixels.len());
+        assert!(pixels.iter().all(|&it| (it as usize) < self.palette.len()), "Colour not in the palette");
+        self.frames.push(lzw(&pixels, self.min_code_size()));
+    }
+
+    pub fn push_grid<T>(&mut self, grid: &Grid<T>, color: impl Fn(Pos, Option<&T>) -> u8) {
+        let mut pixels = Vec::with_capacity(self.width * self.height);
+        for py in 0..self.height {
+            for px in 0..self.width {
+                let pos = (self.origin.0 + (px / self.scale) as i64, self.origin.1 + (py / self.scale) as i64);
+                pixels.push(color(pos, grid.get(pos)));
+            }
+        }
+        self.push(pixels);
+    }
+
+    // The colour table has a power of two size, at least 2
+    fn bits(&self) -> u8 {
+        (1..=8).find(|&bits| 1 << bits >= self.palette.len()).unwrap()
+    }
+
+    fn min_code_size(&self) -> u8 {
+        self.bits().max(2)
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let bits = self.bits();
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend_from_slice(&(self.width as u16).to_le_bytes());
+        gif.extend_from_slice(&(self.height as u16).to_le_bytes());
+        gif.push(0x80 | ((bits - 1) << 4) | (bits - 1)); // global colour table
+        gif.extend_from_slice(&[0, 0]); // background colour, aspect ratio
+        for i in 0..1 << bits {
+            gif.extend_from_slice(self.palette.get(i).unwrap_or(&[0, 0, 0]));
+        }
+        // Loop forever
+        gif.extend_from_slice(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+        for frame in &self.frames {
+            gif.extend_from_slice(&[0x21, 0xf9, 4, 0]);
+            gif.extend_from_slice(&self.delay.to_le_bytes());
+            gif.extend_from_slice(&[0, 0]);
+            gif.push(0x2c);
+            gif.extend_from_slice(&[0, 0, 0, 0]);
+            gif.extend_from_slice(&(self.width as u16).to_le_bytes());
+            gif.extend_from_slice(&(self.height as u16).to_le_bytes());
+            gif.push(0); // no local colour table
+            gif.push(self.min_code_size());
+            for block in frame.chunks(255) {
+                gif.push(block.len() as u8);
+                gif.extend_from_slice(block);
+            }
+            gif.push(0);
+        }
+        gif.push(0x3b);
+        gif
+    }
+}
+
+// Variable width codes packed least significant bit first, starting over when the table is full
+fn lzw(pixels: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+    let mut writer = BitWriter::default();
+    let mut size = min_code_size + 1;
+    let mut next = end + 1;
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    writer.write(clear, size);
+    let mut pixels = pixels.iter().copied();
+    let mut prefix = match pixels.next() {
+        Some(first) => first as u16,
+        None => {
+            writer.write(end, size);
+            return writer.finish()
+        }
+    };
+    for pixel in pixels {
+        if let Some(&code) = table.get(&(prefix, pixel)) {
+            prefix = code;
+            continue
+        }
+        writer.write(prefix, size);
+        if next == 4096 {
+            writer.write(clear, size);
+            table.clear();
+            size = min_code_size + 1;
+            next = end + 1;
+        } else {
+            table.insert((prefix, pixel), next);
+            // The decoder reads the next code wider once it has made this one
+            if next == 1 << size {
+                size += 1;
+            }
+            next += 1;
+        }
+        prefix = pixel as u16;
+    }
+    writer.write(prefix, size);
+    writer.write(end, size);
+    writer.finish()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Follows the GIF spec's description of decoding, to check the encoder against
+    fn unlzw(data: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear = 1usize << min_code_size;
+        let mut size = min_code_size + 1;
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut prev: Option<usize> = None;
+        let mut res = Vec::new();
+        let (mut buffer, mut bits, mut bytes) = (0u32, 0u8, data.iter());
+        loop {
+            while bits < size {
+                buffer |= (*bytes.next().expect("ran out of data") as u32) << bits;
+                bits += 8;
+            }
+            let code = (buffer & ((1 << size) - 1)) as usize;
+            buffer >>= size;
+            bits -= size;
+            if code == clear {
+                table = (0..clear).map(|it| vec![it as u8]).collect();
+                table.push(vec![]);
+                table.push(vec![]);
+                size = min_code_size + 1;
+                prev = None;
+                continue
+            }
+            if code == clear + 1 {
+                return res
+            }
+            let entry = match (prev, table.get(code)) {
+                (_, Some(entry)) => entry.clone(),
+                (Some(p), None) => {
+                    let mut entry = table[p].clone();
+                    entry.push(table[p][0]);
+                    entry
+                }
+                (None, None) => panic!("unknown first code {}", code),
+            };
+            if let Some(p) = prev {
+                let mut new = table[p].clone();
+                new.push(entry[0]);
+                if table.len() < 4096 {
+                    table.push(new);
+                }
+                if table.len() == 1 << size && size < 12 {
+                    size += 1;
+                }
+            }
+            res.extend_from_slice(&entry);
+            prev = Some(code);
+        }
+    }
+
+    #[test]
+    fn compression() {
+        // Example from the spec walkthroughs: 10x10 of three colours
+        let pixels: Vec<u8> = "1111122222111112222211111222221112222222111222222222220000222222220000222222220000011111222000011111222000011111"
+            .bytes().take(100).map(|b| b - b'0').collect();
+        assert_eq!(pixels, unlzw(&lzw(&pixels, 2), 2));
+
+        // Long and varied enough to fill the table and start over
+        let mut seed = 7u32;
+        let noise: Vec<u8> = (0..50000).map(|_| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as u8 % 13
+        }).collect();
+        assert_eq!(noise, unlzw(&lzw(&noise, 4), 4));
+        assert_eq!(Vec::<u8>::new(), unlzw(&lzw(&[], 2), 2));
+    }
+
+    #[test]
+    fn animation() {
+        let grids: Vec<Grid<bool>> = vec![
+            vec![((0, 0), true)].into_iter().collect(),
+            vec![((1, 1), true)].into_iter().collect(),
+        ];
+        let mut animation = Animation::for_grids(((0, 0), (1, 1)), 2, vec![[0, 0, 0], [255, 255, 255]], 10);
+        for grid in &grids {
+            animation.push_grid(grid, |_, it| it.copied().map_or(0, u8::from));
+        }
+        assert_eq!((4, 4, 2), (animation.width, animation.height, animation.len()));
+        assert_eq!(vec![1, 1, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], unlzw(&animation.frames[0], 2));
+
+        let gif = animation.encode();
+        assert_eq!(b"GIF89a\x04\x00\x04\x00\x80\x00\x00\x00\x00\x00\xff\xff\xff", &gif[..19]);
+        assert_eq!(Some(&0x3b), gif.last());
+    }
+}
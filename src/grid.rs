@@ -0,0 +1,317 @@
+use crate::error::Error;
+use std::collections::HashMap;
+use std::iter::FromIterator;
+use std::ops::Index;
+
+// x grows to the right. Whether y grows up or down is up to the puzzle, rendering puts the lowest y
+// first.
+pub type Pos = (i64, i64);
+
+// A grid of tiles, either every cell of a rectangle or just the ones that have been set
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Grid<T> {
+    storage: Storage<T>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Storage<T> {
+    Dense { origin: Pos, width: usize, height: usize, cells: Vec<T> },
+    Sparse(HashMap<Pos, T>),
+}
+
+impl<T> Grid<T> {
+    pub fn sparse() -> Grid<T> {
+        Grid { storage: Storage::Sparse(HashMap::new()) }
+    }
+
+    // Rows from the top, all the same length. (0, 0) is the first cell of the first row.
+    // A ragged row is reported at the first cell it's missing or has spare, there's no text to show.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Grid<T>, Error> {
+        let width = rows.first().map_or(0, Vec::len);
+        if let Some(y) = rows.iter().position(|row| row.len() != width) {
+            let x = rows[y].len().min(width);
+            return Err(parse_error(y, Some(x), "", format!("expected a row {} wide", width)))
+        }
+        let height = rows.len();
+        let cells = rows.into_iter().flatten().collect();
+        Ok(Grid { storage: Storage::Dense { origin: (0, 0), width, height, cells } })
+    }
+
+    // One row per line. tile returns None for characters it doesn't recognize.
+    pub fn parse(text: &str, tile: impl Fn(char) -> Option<T>) -> Result<Grid<T>, Error> {
+        let mut rows = Vec::new();
+        for (y, line) in text.lines().enumerate() {
+            let row = line.chars()
+                .enumerate()
+                .map(|(x, c)| tile(c).ok_or_else(|| parse_error(y, Some(x), line, format!("unknown tile '{}'", c))))
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.push(row);
+        }
+        let width = rows.first().map_or(0, Vec::len);
+        if let Some(y) = rows.iter().position(|row| row.len() != width) {
+            let line = text.lines().nth(y).unwrap_or("");
+            return Err(parse_error(y, None, line, format!("expected a row {} wide", width)))
+        }
+        let height = rows.len();
+        let cells = rows.into_iter().flatten().collect();
+        Ok(Grid { storage: Storage::Dense { origin: (0, 0), width, height, cells } })
+    }
+
+    // Like parse, but leaves out the cells holding blank, so rows can be ragged
+    pub fn parse_sparse(text: &str, blank: char, tile: impl Fn(char) -> Option<T>) -> Result<Grid<T>, Error> {
+        let mut grid = Grid::sparse();
+        for (y, line) in text.lines().enumerate() {
+            for (x, c) in line.chars().enumerate().filter(|&(_, c)| c != blank) {
+                let t = tile(c).ok_or_else(|| parse_error(y, Some(x), line, format!("unknown tile '{}'", c)))?;
+                grid.insert((x as i64, y as i64), t);
+            }
+        }
+        Ok(grid)
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        match &self.storage {
+            Storage::Dense { cells, .. } => self.index_of(pos).map(|i| &cells[i]),
+            Storage::Sparse(cells) => cells.get(&pos),
+        }
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        let i = self.index_of(pos);
+        match &mut self.storage {
+            Storage::Dense { cells, .. } => i.map(move |i| &mut cells[i]),
+            Storage::Sparse(cells) => cells.get_mut(&pos),
+        }
+    }
+
+    pub fn contains(&self, pos: Pos) -> bool {
+        self.get(pos).is_some()
+    }
+
+    // Dense grids can't grow, so setting a cell outside one panics
+    pub fn insert(&mut self, pos: Pos, value: T) {
+        match self.get_mut(pos) {
+            Some(cell) => *cell = value,
+            None => match &mut self.storage {
+                Storage::Sparse(cells) => {
+                    cells.insert(pos, value);
+                }
+                Storage::Dense { .. } => panic!("{:?} is outside the grid", pos),
+            },
+        }
+    }
+
+    fn index_of(&self, (x, y): Pos) -> Option<usize> {
+        match self.storage {
+            Storage::Dense { origin, width, height, .. } => {
+                let (dx, dy) = (x - origin.0, y - origin.1);
+                if dx >= 0 && dy >= 0 && (dx as usize) < width && (dy as usize) < height {
+                    Some(dy as usize * width + dx as usize)
+                } else {
+                    None
+                }
+            }
+            Storage::Sparse(_) => None,
+        }
+    }
+
+    // Dense grids go row by row, sparse ones in no particular order
+    pub fn iter(&self) -> Box<dyn Iterator<Item = (Pos, &T)> + '_> {
+        match &self.storage {
+            Storage::Dense { origin, width, cells, .. } => {
+                let (origin, width) = (*origin, *width as i64);
+                Box::new(cells.iter().enumerate().map(move |(i, t)| ((origin.0 + i as i64 % width, origin.1 + i as i64 / width), t)))
+            }
+            Storage::Sparse(cells) => Box::new(cells.iter().map(|(&pos, t)| (pos, t))),
+        }
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Pos> + '_ {
+        self.iter().map(|(pos, _)| pos)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> + '_ {
+        self.iter().map(|(_, t)| t)
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Dense { cells, .. } => cells.len(),
+            Storage::Sparse(cells) => cells.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn find(&self, pred: impl Fn(&T) -> bool) -> Option<Pos> {
+        self.iter().find(|(_, t)| pred(t)).map(|(pos, _)| pos)
+    }
+
+    pub fn count(&self, pred: impl Fn(&T) -> bool) -> usize {
+        self.values().filter(|t| pred(t)).count()
+    }
+
+    // The smallest and largest x and y of any cell, None if there are none
+    pub fn bounds(&self) -> Option<(Pos, Pos)> {
+        match &self.storage {
+            Storage::Dense { width: 0, .. } | Storage::Dense { height: 0, .. } => None,
+            Storage::Dense { origin, width, height, .. } =>
+                Some((*origin, (origin.0 + *width as i64 - 1, origin.1 + *height as i64 - 1))),
+            Storage::Sparse(cells) => {
+                let min_x = cells.keys().map(|&(x, _)| x).min()?;
+                let min_y = cells.keys().map(|&(_, y)| y).min()?;
+                let max_x = cells.keys().map(|&(x, _)| x).max()?;
+                let max_y = cells.keys().map(|&(_, y)| y).max()?;
+                Some(((min_x, min_y), (max_x, max_y)))
+            }
+        }
+    }
+
+    // The cells next to pos that are in the grid
+    pub fn neighbors4(&self, pos: Pos) -> impl Iterator<Item = (Pos, &T)> + '_ {
+        IntoIterator::into_iter(neighbors4(pos)).filter_map(move |it| self.get(it).map(|t| (it, t)))
+    }
+
+    // Like neighbors4 but including diagonals
+    pub fn neighbors8(&self, pos: Pos) -> impl Iterator<Item = (Pos, &T)> + '_ {
+        IntoIterator::into_iter(neighbors8(pos)).filter_map(move |it| self.get(it).map(|t| (it, t)))
+    }
+
+    // Every cell within the bounds, row by row from the lowest y
+    pub fn rows<U>(&self, f: impl Fn(Pos, Option<&T>) -> U) -> Vec<Vec<U>> {
+        match self.bounds() {
+            Some(((min_x, min_y), (max_x, max_y))) => (min_y..=max_y)
+                .map(|y| (min_x..=max_x).map(|x| f((x, y), self.get((x, y)))).collect())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    // One line per row, each ending in a newline
+    pub fn render(&self, f: impl Fn(Option<&T>) -> char) -> String {
+        self.render_at(|_, t| f(t))
+    }
+
+    pub fn render_at(&self, f: impl Fn(Pos, Option<&T>) -> char) -> String {
+        self.rows(f).into_iter()
+            .map(|row| row.into_iter().chain(std::iter::once('\n')).collect::<String>())
+            .collect()
+    }
+
+    // Same kind of grid, with every cell replaced
+    pub fn map<U>(&self, f: impl Fn(Pos, &T) -> U) -> Grid<U> {
+        let storage = match &self.storage {
+            Storage::Dense { origin, width, height, .. } => Storage::Dense {
+                origin: *origin,
+                width: *width,
+                height: *height,
+                cells: self.iter().map(|(pos, t)| f(pos, t)).collect(),
+            },
+            Storage::Sparse(cells) => Storage::Sparse(cells.iter().map(|(&pos, t)| (pos, f(pos, t))).collect()),
+        };
+        Grid { storage }
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn dense(width: usize, height: usize, fill: T) -> Grid<T> {
+        Grid { storage: Storage::Dense { origin: (0, 0), width, height, cells: vec![fill; width * height] } }
+    }
+
+    // Moves every cell, giving a sparse grid
+    pub fn transform(&self, f: impl Fn(Pos) -> Pos) -> Grid<T> {
+        self.iter().map(|(pos, t)| (f(pos), t.clone())).collect()
+    }
+
+    // Turns a grid with y growing up into one with y growing down, or back
+    pub fn flip_y(&self) -> Grid<T> {
+        self.transform(|(x, y)| (x, -y))
+    }
+}
+
+impl<T> Default for Grid<T> {
+    fn default() -> Grid<T> {
+        Grid::sparse()
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        self.get(pos).unwrap_or_else(|| panic!("Nothing at {:?}", pos))
+    }
+}
+
+impl<T> FromIterator<(Pos, T)> for Grid<T> {
+    fn from_iter<I: IntoIterator<Item = (Pos, T)>>(iter: I) -> Grid<T> {
+        Grid { storage: Storage::Sparse(iter.into_iter().collect()) }
+    }
+}
+
+fn parse_error(y: usize, x: Option<usize>, line: &str, reason: String) -> Error {
+    Error::Parse { file: None, line: y + 1, column: x.map(|x| x + 1), text: line.to_string(), reason }
+}
+
+pub fn neighbors4((x, y): Pos) -> [Pos; 4] {
+    [(x + 1, y), (x, y + 1), (x - 1, y), (x, y - 1)]
+}
+
+pub fn neighbors8((x, y): Pos) -> [Pos; 8] {
+    [(x + 1, y), (x + 1, y + 1), (x, y + 1), (x - 1, y + 1), (x - 1, y), (x - 1, y - 1), (x, y - 1), (x + 1, y - 1)]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use itertools::Itertools;
+
+    fn tile(c: char) -> Option<bool> {
+        match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn dense() {
+        let grid = Grid::parse("#..\n.##", tile).unwrap();
+        assert_eq!(6, grid.len());
+        assert_eq!(Some(((0, 0), (2, 1))), grid.bounds());
+        assert!(grid[(2, 1)]);
+        assert_eq!(None, grid.get((3, 1)));
+        assert_eq!(vec![(0, 0), (1, 1), (2, 1)], grid.iter().filter(|(_, &t)| t).map(|(pos, _)| pos).collect::<Vec<_>>());
+        assert_eq!(vec![((2, 1), &true), ((0, 1), &false), ((1, 0), &false)], grid.neighbors4((1, 1)).collect::<Vec<_>>());
+        assert_eq!(5, grid.neighbors8((1, 1)).count());
+        assert_eq!("#..\n.##\n", grid.render(|t| if t == Some(&true) { '#' } else { '.' }));
+        assert_eq!(Some((1, 1)), grid.map(|(x, _), &t| t && x > 0).find(|&t| t));
+    }
+
+    #[test]
+    fn sparse() {
+        let mut grid = Grid::sparse();
+        grid.insert((-2, 3), 'a');
+        grid.insert((1, 4), 'b');
+        assert_eq!(Some(((-2, 3), (1, 4))), grid.bounds());
+        assert_eq!("a   \n   b\n", grid.render(|t| t.copied().unwrap_or(' ')));
+        let flipped = grid.flip_y();
+        assert_eq!(Some(&'a'), flipped.get((-2, -3)));
+        assert_eq!("   b\na   \n", flipped.render(|t| t.copied().unwrap_or(' ')));
+        assert_eq!(Some(&'b'), grid.transform(|(x, y)| (x + 1, y - 4)).get((2, 0)));
+        assert_eq!(None, Grid::<char>::sparse().bounds());
+    }
+
+    #[test]
+    fn parse_errors() {
+        let e = Grid::parse("#.\n#x", tile).err().unwrap();
+        assert_eq!("<input>:2:2: unknown tile 'x'\n  #x\n   ^", e.to_string());
+        assert!(Grid::parse("#.\n#", tile).is_err());
+        let e = Grid::from_rows(vec![vec![1, 2], vec![3, 4, 5]]).err().unwrap();
+        assert_eq!(Error::Parse { file: None, line: 2, column: Some(3), text: String::new(), reason: "expected a row 2 wide".to_string() }, e);
+        let grid = Grid::parse_sparse(" #\n#", ' ', tile).unwrap();
+        assert_eq!(vec![(0, 1), (1, 0)], grid.positions().sorted().collect::<Vec<_>>());
+    }
+}
@@ -1,13 +1,25 @@
 use std::collections::VecDeque;
 use std::rc::Rc;
 use std::cell::RefCell;
+use crate::error::{self, Error, ParseError};
 use crate::intcode::ComputerState::{Halted, WaitingOnInput};
 
 pub type Value = i64;
 pub type Program = Vec<Value>;
 
-pub fn parse_program(s: &str) -> Program {
-    s.split(',').map(|it| it.trim().parse().unwrap()).collect()
+pub fn parse_program(s: &str) -> Result<Program, ParseError> {
+    error::split_offsets(s, ",")
+        .map(|(offset, it)| {
+            let value = it.trim_start();
+            error::parse_number(value.trim_end(), offset + it.len() - value.len() + 1)
+        })
+        .collect()
+}
+
+// The program on the first line of a puzzle's input
+pub fn read_program(input: &[String]) -> Result<Program, Error> {
+    let line = input.first().ok_or_else(|| Error::Input("Expected an Intcode program but the input is empty".to_string()))?;
+    parse_program(line).map_err(|e| e.on_line(1, line))
 }
 
 pub fn execute_no_io(program: &mut Program) {
@@ -32,6 +44,79 @@ pub fn execute(program: &mut Program, input: &mut dyn Iterator<Item=&Value>) ->
     r
 }
 
+// Anything that can run a program, so other implementations can be checked against the interpreter
+pub trait Engine {
+    fn name(&self) -> &str;
+    // Runs until the program halts, needs more input than it was given or has executed max_steps
+    // instructions
+    fn run(&self, program: &Program, input: &[Value], max_steps: usize) -> Run;
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Run {
+    pub output: Vec<Value>,
+    pub memory: Program,
+    // None if it ran out of steps
+    pub state: Option<ComputerState>,
+}
+
+pub struct Interpreter;
+
+impl Engine for Interpreter {
+    fn name(&self) -> &str {
+        "interpreter"
+    }
+
+    fn run(&self, program: &Program, input: &[Value], max_steps: usize) -> Run {
+        let mut comp = Computer::new(program.clone());
+        comp.set_input(Some(Rc::new(RefCell::new(Stream::from_iter(&mut input.iter())))));
+        let output = Stream::new_wrapped();
+        comp.set_output(Some(Rc::clone(&output)));
+        let state = comp.execute_limited(max_steps);
+        let output = output.borrow_mut().read_all();
+        Run { output, memory: comp.program, state }
+    }
+}
+
+// The same computer resumed one instruction at a time and only given input when it asks for it, which
+// takes paths through execute_limited that a single long run never does
+pub struct Stepper;
+
+impl Engine for Stepper {
+    fn name(&self) -> &str {
+        "stepper"
+    }
+
+    fn run(&self, program: &Program, input: &[Value], max_steps: usize) -> Run {
+        let mut comp = Computer::new(program.clone());
+        let pending = Stream::new_wrapped();
+        let output = Stream::new_wrapped();
+        comp.set_input(Some(Rc::clone(&pending)));
+        comp.set_output(Some(Rc::clone(&output)));
+        let mut input = input.iter();
+        let mut steps = 0;
+        let mut state = None;
+        while steps < max_steps {
+            match comp.execute_limited(1) {
+                None => steps += 1,
+                Some(WaitingOnInput) => match input.next() {
+                    Some(&value) => pending.borrow_mut().write(value),
+                    None => {
+                        state = Some(WaitingOnInput);
+                        break
+                    }
+                },
+                Some(Halted) => {
+                    state = Some(Halted);
+                    break
+                }
+            }
+        }
+        let output = output.borrow_mut().read_all();
+        Run { output, memory: comp.program, state }
+    }
+}
+
 fn read_param_mode(instr: Value, place: i32) -> i32 {
     (instr as i32) % place / (place / 10)
 }
@@ -72,8 +157,14 @@ impl Computer {
     }
 
     pub fn execute(&mut self) -> ComputerState {
+        self.execute_limited(usize::MAX).expect("Ran out of steps")
+    }
+
+    // Returns None if it executed max_steps instructions without halting or waiting, in which case it
+    // can be resumed
+    pub fn execute_limited(&mut self, max_steps: usize) -> Option<ComputerState> {
         let mut ip: usize = self.ip;
-        loop {
+        for _ in 0..max_steps {
             let instr = self.program[ip];
             log::trace!("ip: {} instr: {}", ip, instr);
             let op = instr % 100;
@@ -103,7 +194,7 @@ impl Computer {
                         Some(x) => x,
                         None => {
                             self.ip = ip;
-                            return WaitingOnInput
+                            return Some(WaitingOnInput)
                         },
                     };
                     log::trace!("got an input: {}", inp);
@@ -157,11 +248,13 @@ impl Computer {
                 }
                 99 => {
                     self.ip = ip;
-                    return Halted
+                    return Some(Halted)
                 },
                 _ => panic!("Unrecognized opcode: {} @ ip {}", op, ip)
             }
         }
+        self.ip = ip;
+        None
     }
 
     fn read(&self, param: Value, mode: i32) -> Value {
@@ -186,20 +279,32 @@ impl Computer {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum ComputerState {
     Halted,
     WaitingOnInput
 }
 
 pub struct Stream {
-    store: VecDeque<Value>
+    store: VecDeque<Value>,
+    // Totals since the stream was made, which unlike the length can't be confused by reads and writes
+    // happening together
+    reads: usize,
+    writes: usize,
+}
+
+impl Default for Stream {
+    fn default() -> Stream {
+        Stream::new()
+    }
 }
 
 impl Stream {
     pub fn new() -> Stream {
         Stream {
-            store: VecDeque::new()
+            store: VecDeque::new(),
+            reads: 0,
+            writes: 0,
         }
     }
 
@@ -208,19 +313,43 @@ impl Stream {
     }
 
     fn from_iter(iter: &mut dyn Iterator<Item=&Value>) -> Stream {
+        let store: VecDeque<Value> = iter.copied().collect();
         Stream {
-            store: iter.copied().collect()
+            writes: store.len(),
+            store,
+            reads: 0,
         }
     }
 
     pub fn read(&mut self) -> Option<Value> {
-        self.store.pop_front()
+        let value = self.store.pop_front();
+        if value.is_some() {
+            self.reads += 1;
+        }
+        value
     }
 
     pub fn write(&mut self, value: Value) {
+        self.writes += 1;
         self.store.push_back(value)
     }
 
+    pub fn reads(&self) -> usize {
+        self.reads
+    }
+
+    pub fn writes(&self) -> usize {
+        self.writes
+    }
+
+    pub fn len(&self) -> usize {
+        self.store.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.store.is_empty()
+    }
+
     pub fn read_all(&mut self) -> Vec<Value> {
         let mut res = Vec::new();
         while let Some(v) = self.read() {
@@ -261,6 +390,15 @@ mod test {
         assert_eq!(vec![30,1,1,4,2,5,6,0,99], program);
     }
 
+    #[test]
+    fn parse() {
+        assert_eq!(Ok(vec![1, -2, 99]), parse_program("1, -2,99"));
+        assert_eq!(Some(6), parse_program("1, 2,x3,99").err().unwrap().column);
+        assert_eq!(Ok(vec![1, 2, 3]), read_program(&["1,2,3\r".to_string()]));
+        let e = read_program(&["1,,3".to_string()]).err().unwrap();
+        assert_eq!("<input>:1:3: bad number '': cannot parse integer from empty string\n  1,,3\n    ^", e.to_string());
+    }
+
     #[test]
     fn param_modes() {
         assert_eq!(0, read_param_mode(23, 1000));
@@ -275,7 +413,7 @@ mod test {
 
     #[test]
     fn jumps() {
-        let program = parse_program("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9");
+        let program = parse_program("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9").unwrap();
         assert_eq!(vec![0], execute(&mut program.clone(), &mut [0].iter()));
         assert_eq!(vec![1], execute(&mut program.clone(), &mut [1].iter()));
         assert_eq!(vec![1], execute(&mut program.clone(), &mut [2].iter()));
@@ -286,7 +424,7 @@ mod test {
         let program = parse_program(
             "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,
              1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,
-             999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99");
+             999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99").unwrap();
         assert_eq!(vec![999], execute(&mut program.clone(), &mut [2].iter()));
         assert_eq!(vec![1000], execute(&mut program.clone(), &mut [8].iter()));
         assert_eq!(vec![1001], execute(&mut program.clone(), &mut [42].iter()));
@@ -294,7 +432,7 @@ mod test {
 
     #[test]
     fn large() {
-        let mut program = parse_program("104,1125899906842624,99");
+        let mut program = parse_program("104,1125899906842624,99").unwrap();
         assert_eq!(vec![1125899906842624], execute(&mut program, &mut[].iter()))
     }
 }
@@ -0,0 +1,16 @@
+use crate::config::Config;
+use std::io::{stdin, stdout, Write};
+
+// Puzzles that can be watched or played by hand only do so when the Config says so, otherwise they
+// have to solve themselves
+pub fn enabled(config: &Config) -> bool {
+    config.interactive
+}
+
+pub fn prompt(prompt: &str) -> String {
+    print!("{}", prompt);
+    stdout().flush().unwrap();
+    let mut line = String::new();
+    stdin().read_line(&mut line).unwrap();
+    line
+}
@@ -0,0 +1,64 @@
+#[macro_use] extern crate itertools;
+
+use std::str::FromStr;
+
+use crate::error::{Error, ParseError};
+
+#[macro_use] pub mod solution;
+pub mod intcode;
+pub mod answer;
+pub mod circuit;
+pub mod config;
+pub mod conformance;
+pub mod cycle;
+pub mod error;
+mod export;
+pub mod fuzz;
+mod gif;
+pub mod grid;
+mod interactive;
+pub mod math;
+pub mod run_all;
+mod ocr;
+mod parallel;
+pub mod params;
+mod png;
+mod pnm;
+pub mod search;
+mod supervisor;
+mod symbolic;
+mod visualize;
+
+mod day1;
+mod day2;
+mod day3;
+mod day4;
+mod day5;
+mod day6;
+mod day7;
+mod day8;
+mod day9;
+mod day10;
+mod day11;
+mod day12;
+mod day13;
+mod day14;
+mod day15;
+mod day16;
+mod day17;
+mod day18;
+mod day19;
+mod day20;
+mod day21;
+mod day22;
+mod day23;
+mod day24;
+mod day25;
+
+// Each line of the input as a T
+pub fn parse_input<T>(input: &str) -> Result<Vec<T>, Error> where T: FromStr, T::Err: Into<ParseError> {
+    input.lines()
+        .enumerate()
+        .map(|(i, line)| line.parse().map_err(|e: T::Err| e.into().on_line(i + 1, line)))
+        .collect()
+}
@@ -1,45 +1,81 @@
-#[macro_use] extern crate itertools;
-
-use std::fmt::Debug;
-use std::str::FromStr;
-
 use clap::{App, Arg};
 use log::Level;
 
-mod intcode;
-
-mod day1;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day20;
-mod day21;
-mod day22;
-mod day23;
-mod day24;
-mod day25;
+use aoc2019::config::Config;
+use aoc2019::params::{self, Params};
+use aoc2019::{answer, conformance, fuzz, intcode, run_all, solution};
 
 fn main() {
+    let params_help = params::help();
     let matches = App::new("aoc2019")
         .about("Advent of Code 2019")
+        .after_help(params_help.as_str())
         .arg(Arg::with_name("puzzle")
-            .help("Puzzle number, e.g. 2-1 for day 2, puzzle 1")
+            .help("Puzzle number, e.g. 2-1 for day 2, puzzle 1, all to run and check every puzzle, list to show them all, conformance to check the Intcode interpreter or fuzz to compare Intcode engines")
             .required(true))
+        .arg(Arg::with_name("input")
+            .long("input")
+            .help("Read the puzzle input from this file, or - for stdin, instead of the input directory. For conformance, the directory of cases to check")
+            .takes_value(true))
+        .arg(Arg::with_name("input_dir")
+            .long("input-dir")
+            .help("Directory holding the puzzle inputs, defaults to ./input")
+            .env("AOC_INPUT_DIR")
+            .takes_value(true))
+        .arg(Arg::with_name("format")
+            .long("format")
+            .help("How to print a puzzle's answer, json adds the day, part and elapsed time")
+            .takes_value(true)
+            .possible_values(&["plain", "json"])
+            .default_value("plain"))
+        .arg(Arg::with_name("param")
+            .long("param")
+            .help("Change one of the puzzle's parameters, listed below, from its default")
+            .takes_value(true)
+            .value_name("name=value")
+            .multiple(true)
+            .number_of_values(1))
+        .arg(Arg::with_name("interactive")
+            .long("interactive")
+            .help("Watch or play the puzzles that allow it instead of letting them solve themselves"))
+        .arg(Arg::with_name("visualize")
+            .long("visualize")
+            .help("Animate the puzzles that draw frames in the terminal"))
+        .arg(Arg::with_name("record")
+            .long("record")
+            .help("Record the frames a puzzle draws to this asciicast v2 file")
+            .takes_value(true))
+        .arg(Arg::with_name("gif")
+            .long("gif")
+            .help("Save an animated GIF to this file, for the puzzles that draw one (12-1, 15-2, 18-1 and 24-1)")
+            .takes_value(true))
+        .arg(Arg::with_name("export")
+            .long("export")
+            .help("Write the images and captures puzzles can export (8-2, 11-1, 11-2, 23-1 and 23-2) into this directory")
+            .takes_value(true)
+            .value_name("dir"))
+        .arg(Arg::with_name("fps")
+            .long("fps")
+            .help("Frames per second when animating or recording [default: 30]")
+            .takes_value(true)
+            .validator(|fps| match fps.parse::<f64>() {
+                Ok(fps) if fps > 0.0 => Ok(()),
+                _ => Err(String::from("must be a positive number")),
+            }))
+        .arg(Arg::with_name("jobs")
+            .long("jobs")
+            .short("j")
+            .help("Threads to split work between, for all and the puzzles that can [default: one per core]")
+            .takes_value(true)
+            .validator(|jobs| match jobs.parse::<usize>() {
+                Ok(jobs) if jobs > 0 => Ok(()),
+                _ => Err(String::from("must be a positive whole number")),
+            }))
+        .arg(Arg::with_name("seed")
+            .long("seed")
+            .help("First seed for fuzz")
+            .takes_value(true)
+            .default_value("0"))
         .arg(Arg::with_name("log_level")
             .long("level")
             .help("Logging level")
@@ -59,70 +95,84 @@ fn main() {
 
     simple_logger::init_with_level(log_level).unwrap();
 
+    let path = |name| matches.value_of(name).map(std::path::PathBuf::from);
+    let mut config = Config {
+        params: Params::default(),
+        input_dir: path("input_dir"),
+        interactive: matches.is_present("interactive"),
+        visualize: matches.is_present("visualize"),
+        record: path("record"),
+        fps: matches.value_of("fps").map(|it| it.parse().unwrap()),
+        gif: path("gif"),
+        export_dir: path("export"),
+        jobs: matches.value_of("jobs").map(|it| it.parse().unwrap()),
+    };
+
+    if matches.value_of("puzzle") == Some("all") {
+        if matches.is_present("param") {
+            eprintln!("--param only applies to a single puzzle, all checks the answers with the defaults");
+            std::process::exit(1)
+        }
+        // Every puzzle would wait on the one terminal, or save over the one GIF
+        if let Some(flag) = ["interactive", "gif"].iter().find(|&&it| matches.is_present(it)) {
+            eprintln!("--{} only applies to a single puzzle", flag);
+            std::process::exit(1)
+        }
+        // Frames from puzzles running at once would interleave
+        if config.visualize || config.record.is_some() {
+            config.jobs = Some(1);
+        }
+        let passed = run_all::run(std::path::Path::new(run_all::ANSWERS), &config);
+        config.finish();
+        std::process::exit(if passed { 0 } else { 1 })
+    }
+
     println!("{}", match matches.value_of("puzzle").unwrap() {
-        "1-1" => execute("1.txt", day1::run1),
-        "1-2" => execute("1.txt", day1::run2),
-        "2-1" => execute("2.txt", day2::run1),
-        "2-2" => execute("2.txt", day2::run2),
-        "3-1" => execute("3.txt", day3::run1),
-        "3-2" => execute("3.txt", day3::run2),
-        "4-1" => day4::run1(),
-        "4-2" => day4::run2(),
-        "5-1" => execute("5.txt", day5::run1),
-        "5-2" => execute("5.txt", day5::run2),
-        "6-1" => execute("6.txt", day6::run1),
-        "6-2" => execute("6.txt", day6::run2),
-        "7-1" => execute("7.txt", day7::run1),
-        "7-2" => execute("7.txt", day7::run2),
-        "8-1" => execute("8.txt", day8::run1),
-        "8-2" => execute("8.txt", day8::run2),
-        "9-1" => execute("9.txt", day9::run1),
-        "9-2" => execute("9.txt", day9::run2),
-        "10-1" => execute("10.txt", day10::run1),
-        "10-2" => execute("10.txt", day10::run2),
-        "11-1" => execute("11.txt", day11::run1),
-        "11-2" => execute("11.txt", day11::run2),
-        "12-1" => execute("12.txt", day12::run1),
-        "12-2" => execute("12.txt", day12::run2),
-        "13-1" => execute("13.txt", day13::run1),
-        "13-2" => execute("13.txt", day13::run2),
-        "14-1" => execute("14.txt", day14::run1),
-        "14-2" => execute("14.txt", day14::run2),
-        "15-1" => execute("15.txt", day15::run1),
-        "15-2" => execute("15.txt", day15::run2),
-        "16-1" => execute("16.txt", day16::run1),
-        "16-2" => execute("16.txt", day16::run2),
-        "17-1" => execute("17.txt", day17::run1),
-        "17-2" => execute("17.txt", day17::run2),
-        "18-1" => execute("18.txt", day18::run1),
-        "18-2" => execute("18-2.txt", day18::run2),
-        "19-1" => execute("19.txt", day19::run1),
-        "19-2" => execute("19.txt", day19::run2),
-        "20-1" => execute("20.txt", day20::run1),
-        "20-2" => execute("20.txt", day20::run2),
-        "21-1" => execute("21.txt", day21::run1),
-        "21-2" => execute("21.txt", day21::run2),
-        "22-1" => execute("22.txt", day22::run1),
-        "22-2" => execute("22.txt", day22::run2),
-        "23-1" => execute("23.txt", day23::run1),
-        "23-2" => execute("23.txt", day23::run2),
-        "24-1" => execute("24.txt", day24::run1),
-        "24-2" => execute("24.txt", day24::run2),
-        "25-1" => execute("25.txt", day25::run1),
-        "25-2" => execute("25.txt", day25::run2),
-        _ => "No puzzle with that number".to_string()
+        "list" => solution::list(),
+        "conformance" => {
+            let dir = matches.value_of("input").unwrap_or(conformance::DIR);
+            passed_or_exit(conformance::report(&intcode::Interpreter, std::path::Path::new(dir)))
+        }
+        "fuzz" => {
+            let seed = matches.value_of("seed").unwrap().parse().expect("Seed must be a number");
+            passed_or_exit(fuzz::report(&[&intcode::Interpreter, &intcode::Stepper], seed, 1000))
+        }
+        id => match solution::find(id) {
+            Ok(solution) => {
+                let assignments: Vec<&str> = matches.values_of("param").map_or_else(Vec::new, |it| it.collect());
+                match Params::check(solution, &assignments) {
+                    Ok(params) => config.params = params,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        std::process::exit(1)
+                    }
+                }
+                let source = matches.value_of("input").map_or(solution::Source::InputDir, solution::Source::from_arg);
+                let start = std::time::Instant::now();
+                let result = solution::execute(solution, &source, &config);
+                config.finish();
+                match result {
+                    Ok(answer) if matches.value_of("format") == Some("json") =>
+                        answer::to_json(solution.day(), solution.part(), &answer, start.elapsed().as_secs_f64() * 1000.0),
+                    Ok(answer) => answer.to_string(),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        std::process::exit(1)
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1)
+            }
+        }
     })
 }
 
-fn execute<F, T: FromStr, R: Debug>(input_path: &str, f: F) -> String
-        where F: FnOnce(Vec<T>) -> R, T::Err : Debug
-{
-    let input = std::fs::read_to_string(format!("input/{}", input_path)).unwrap();
-    format!("{:#?}", f(parse_input(&input)))
-}
-
-pub fn parse_input<T>(input: &str) -> Vec<T> where T: FromStr, T::Err : Debug {
-    input.lines()
-        .map(|it| it.parse().expect(&format!("Could not parse input: {}", it)))
-        .collect()
+// The report to print if the check passed, otherwise prints it and exits with 1 like a failed puzzle
+fn passed_or_exit(report: Result<String, String>) -> String {
+    report.unwrap_or_else(|report| {
+        println!("{}", report);
+        std::process::exit(1)
+    })
 }
@@ -0,0 +1,130 @@
+// Number theory shared between the puzzles. Results are never negative and moduli must be positive.
+
+pub fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+pub fn lcm(a: i64, b: i64) -> i64 {
+    if a == 0 || b == 0 {
+        0
+    } else {
+        (a / gcd(a, b) * b).abs()
+    }
+}
+
+pub fn gcd_all(values: impl IntoIterator<Item = i64>) -> i64 {
+    values.into_iter().fold(0, gcd)
+}
+
+pub fn lcm_all(values: impl IntoIterator<Item = i64>) -> i64 {
+    values.into_iter().fold(1, lcm)
+}
+
+// Returns (g, x, y) where a*x + b*y = g = gcd(a, b)
+pub fn ext_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    let (mut r0, mut r1) = (a, b);
+    let (mut x0, mut x1) = (1, 0);
+    let (mut y0, mut y1) = (0, 1);
+    while r1 != 0 {
+        let q = r0 / r1;
+        let (r, x, y) = (r0 - q * r1, x0 - q * x1, y0 - q * y1);
+        r0 = r1;
+        r1 = r;
+        x0 = x1;
+        x1 = x;
+        y0 = y1;
+        y1 = y;
+    }
+    if r0 < 0 {
+        (-r0, -x0, -y0)
+    } else {
+        (r0, x0, y0)
+    }
+}
+
+pub fn mod_inverse(a: i64, m: i64) -> Option<i64> {
+    let (g, x, _) = ext_gcd(a.rem_euclid(m), m);
+    if g == 1 {
+        Some(x.rem_euclid(m))
+    } else {
+        None
+    }
+}
+
+pub fn mod_mul(a: i64, b: i64, m: i64) -> i64 {
+    (a as i128 * b as i128).rem_euclid(m as i128) as i64
+}
+
+pub fn mod_pow(base: i64, mut exp: u64, m: i64) -> i64 {
+    let mut base = base.rem_euclid(m);
+    let mut result = 1 % m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mod_mul(result, base, m);
+        }
+        base = mod_mul(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+// Chinese remainder theorem: the x with x = r (mod m) for every (r, m), as (x, lcm of the moduli).
+// The moduli don't need to be coprime, but None is returned if the congruences contradict each other.
+pub fn crt(congruences: &[(i64, i64)]) -> Option<(i64, i64)> {
+    congruences.iter().try_fold((0, 1), |(x, m), &(r, n)| {
+        let (g, p, _) = ext_gcd(m, n);
+        let diff = r - x;
+        if diff % g != 0 {
+            return None
+        }
+        let l = m / g * n;
+        let k = mod_mul(diff / g, p, n / g);
+        Some(((x + mod_mul(m, k, l)).rem_euclid(l), l))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn divisors() {
+        assert_eq!(4, gcd(12, 8));
+        assert_eq!(4, gcd(8, -12));
+        assert_eq!(7, gcd(0, 7));
+        assert_eq!(0, gcd(0, 0));
+        assert_eq!(24, lcm(12, 8));
+        assert_eq!(0, lcm(0, 8));
+        assert_eq!(6, gcd_all(vec![12, 18, 30]));
+        assert_eq!(2772, lcm_all(vec![18, 28, 44]));
+        // Big enough that dividing by the gcd twice would go wrong
+        assert_eq!(4686774924, lcm_all(vec![2028, 5898, 4702]));
+    }
+
+    #[test]
+    fn modular() {
+        let (g, x, y) = ext_gcd(240, 46);
+        assert_eq!((2, 2), (g, 240 * x + 46 * y));
+        assert_eq!(Some(4), mod_inverse(3, 11));
+        assert_eq!(Some(7), mod_inverse(-3, 11));
+        assert_eq!(None, mod_inverse(4, 8));
+        assert_eq!(1, mod_mul(119315717514046, 119315717514046, 119315717514047));
+        assert_eq!(445, mod_pow(4, 13, 497));
+        assert_eq!(0, mod_pow(5, 0, 1));
+        let p = 119315717514047;
+        assert_eq!(1, mod_mul(mod_pow(12345, p as u64 - 2, p), 12345, p));
+    }
+
+    #[test]
+    fn chinese_remainder() {
+        assert_eq!(Some((1068781, 3162341)), crt(&[(0, 7), (12, 13), (55, 59), (25, 31), (12, 19)]));
+        assert_eq!(Some((10, 12)), crt(&[(2, 4), (4, 6)]));
+        assert_eq!(None, crt(&[(1, 4), (2, 6)]));
+    }
+}
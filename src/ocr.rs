@@ -0,0 +1,107 @@
+use crate::answer::Answer;
+
+const HEIGHT: usize = 6;
+
+const FONT: [(char, [&str; HEIGHT]); 18] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', ["###", ".#.", ".#.", ".#.", ".#.", "###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+// Reads the block letters that AoC draws. Letters are separated by at least one blank column.
+pub fn recognize(rows: &[Vec<bool>]) -> Result<String, String> {
+    let rows: Vec<&Vec<bool>> = rows.iter()
+        .skip_while(|row| !row.contains(&true))
+        .collect();
+    let end = rows.iter().rposition(|row| row.contains(&true)).map(|it| it + 1).unwrap_or(0);
+    let rows = &rows[..end];
+    if rows.len() != HEIGHT {
+        return Err(format!("Expected letters {} pixels high but found {}", HEIGHT, rows.len()))
+    }
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let lit = |x: usize, y: usize| rows[y].get(x).copied().unwrap_or(false);
+
+    let mut result = String::new();
+    let mut x = 0;
+    while x < width {
+        if (0..HEIGHT).all(|y| !lit(x, y)) {
+            x += 1;
+            continue
+        }
+        let start = x;
+        while x < width && (0..HEIGHT).any(|y| lit(x, y)) {
+            x += 1;
+        }
+        let glyph: Vec<String> = (0..HEIGHT)
+            .map(|y| (start..x).map(|gx| if lit(gx, y) { '#' } else { '.' }).collect())
+            .collect();
+        let letter = FONT.iter()
+            .find(|(_, pattern)| pattern.iter().zip(&glyph).all(|(p, g)| p == g))
+            .map(|&(c, _)| c)
+            .ok_or_else(|| format!("Unrecognized letter at column {}:\n{}", start, glyph.join("\n")))?;
+        result.push(letter);
+    }
+    Ok(result)
+}
+
+// The letters if they can be read, otherwise the picture itself
+pub fn answer(rows: Vec<Vec<bool>>) -> Answer {
+    match recognize(&rows) {
+        Ok(text) => Answer::Text(text),
+        Err(e) => {
+            log::warn!("{}", e);
+            Answer::Image(rows)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::grid::Grid;
+
+    fn to_rows(s: &str) -> Vec<Vec<bool>> {
+        s.lines().map(|line| line.chars().map(|c| c == '#').collect()).collect()
+    }
+
+    #[test]
+    fn letters() {
+        let image = to_rows("
+####.#....###....##.####.
+...#.#....#..#....#.#....
+..#..#....###.....#.###..
+.#...#....#..#....#.#....
+#....#....#..#.#..#.#....
+####.####.###...##..#....
+");
+        assert_eq!(Ok("ZLBJF".to_string()), recognize(&image));
+    }
+
+    #[test]
+    fn grid() {
+        let grid = Grid::parse(&FONT[0].1.join("\n"), |c| Some(c == '#')).unwrap().transform(|(x, y)| (x - 10, y + 3));
+        assert_eq!(Ok("A".to_string()), recognize(&grid.rows(|_, it| it == Some(&true))));
+    }
+
+    #[test]
+    fn unknown() {
+        let image = to_rows("#\n#\n#\n#\n#\n#");
+        assert!(recognize(&image).is_err());
+        assert_eq!(Answer::Image(image.clone()), answer(image));
+    }
+}
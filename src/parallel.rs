@@ -0,0 +1,106 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+
+// Splits independent work between a pool of jobs threads. Results always come back in the order of the
+// items, so the answers don't depend on how many threads there are or which finishes first.
+// Calls each with the results in item order, as soon as all the earlier ones are done.
+pub fn for_each_ordered<T, R>(jobs: usize, items: Vec<T>, work: impl Fn(T) -> R + Sync, mut each: impl FnMut(usize, R))
+    where T: Send, R: Send {
+    let workers = jobs.min(items.len());
+    if workers <= 1 {
+        items.into_iter().map(work).enumerate().for_each(|(i, it)| each(i, it));
+        return
+    }
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let sender = sender.clone();
+            let (queue, work) = (&queue, &work);
+            scope.spawn(move || {
+                // Taking the lock in its own statement releases it before the work starts
+                loop {
+                    let next = queue.lock().unwrap().next();
+                    let (i, item) = match next {
+                        Some(it) => it,
+                        None => break
+                    };
+                    if sender.send((i, work(item))).is_err() {
+                        break
+                    }
+                }
+            });
+        }
+        drop(sender);
+        let mut done = Vec::new();
+        let mut next = 0;
+        for (i, result) in receiver {
+            if i >= done.len() {
+                done.resize_with(i + 1, || None);
+            }
+            done[i] = Some(result);
+            while let Some(result) = done.get_mut(next).and_then(Option::take) {
+                each(next, result);
+                next += 1;
+            }
+        }
+    });
+}
+
+pub fn map<T, R>(jobs: usize, items: Vec<T>, work: impl Fn(T) -> R + Sync) -> Vec<R> where T: Send, R: Send {
+    let mut res = Vec::with_capacity(items.len());
+    for_each_ordered(jobs, items, work, |_, it| res.push(it));
+    res
+}
+
+// The result for the earliest item that has one, the same as a serial find_map. Items after one that
+// has already succeeded are skipped.
+pub fn find_map<T, R>(jobs: usize, items: Vec<T>, work: impl Fn(T) -> Option<R> + Sync) -> Option<R> where T: Send, R: Send {
+    let found = AtomicUsize::new(usize::MAX);
+    let items: Vec<(usize, T)> = items.into_iter().enumerate().collect();
+    let mut res = None;
+    for_each_ordered(jobs, items, |(i, item)| {
+        if i > found.load(Ordering::Relaxed) {
+            return None
+        }
+        let result = work(item);
+        if result.is_some() {
+            found.fetch_min(i, Ordering::Relaxed);
+        }
+        result
+    }, |_, result| {
+        if res.is_none() {
+            res = result;
+        }
+    });
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ordered() {
+        // Earlier items take longer, so they finish out of order
+        let items: Vec<u64> = (0..40).collect();
+        let slow = |it: u64| {
+            thread::sleep(std::time::Duration::from_millis(40 - it));
+            it * it
+        };
+        assert_eq!(items.iter().map(|it| it * it).collect::<Vec<_>>(), map(4, items.clone(), slow));
+        let mut seen = Vec::new();
+        for_each_ordered(4, items, slow, |i, it| seen.push((i, it)));
+        assert!(seen.iter().enumerate().all(|(i, &(j, it))| i == j && it == (i * i) as u64));
+        assert_eq!(Vec::<u64>::new(), map(4, Vec::new(), slow));
+    }
+
+    #[test]
+    fn first() {
+        let items: Vec<u32> = (0..1000).collect();
+        assert_eq!(Some(49), find_map(4, items.clone(), |it| if it % 7 == 0 && it > 45 { Some(it) } else { None }));
+        assert_eq!(None, find_map(4, items, |it| if it > 1000 { Some(it) } else { None }));
+    }
+}
@@ -0,0 +1,133 @@
+use crate::error::Error;
+use crate::solution::{self, Solution};
+use std::fmt::Display;
+use std::str::FromStr;
+
+// Numbers a puzzle would otherwise hard-code, like sizes and step counts, so it can be run on other
+// variants with --param name=value
+#[derive(Debug)]
+pub struct Param {
+    pub name: &'static str,
+    pub default: &'static str,
+    pub kind: Kind,
+    pub help: &'static str,
+}
+
+// The values a parameter takes, all whole numbers
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Kind {
+    // 0 or more
+    Count,
+    // 1 or more
+    Positive,
+}
+
+impl Kind {
+    fn check(self, value: &str) -> Result<(), String> {
+        let n: i64 = value.parse().map_err(|e| format!("{}", e))?;
+        let min = match self {
+            Kind::Count => 0,
+            Kind::Positive => 1,
+        };
+        if n < min {
+            return Err(format!("must be at least {}", min))
+        }
+        Ok(())
+    }
+}
+
+// The name=value pairs given for one puzzle, already checked against the parameters it declares
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Params(Vec<(String, String)>);
+
+impl Params {
+    pub fn check(solution: &dyn Solution, assignments: &[&str]) -> Result<Params, Error> {
+        let names: Vec<&str> = solution.params().iter().map(|it| it.name).collect();
+        let mut res = Vec::new();
+        for assignment in assignments {
+            let (name, value) = assignment.split_once('=')
+                .ok_or_else(|| Error::Input(format!("Expected name=value, not '{}'", assignment)))?;
+            let (name, value) = (name.trim(), value.trim());
+            if names.is_empty() {
+                return Err(Error::Input(format!("{} doesn't take any parameters", solution.id())))
+            }
+            let param = solution.params().iter().find(|it| it.name == name)
+                .ok_or_else(|| Error::Input(format!("{} has no parameter '{}', it takes {}", solution.id(), name, names.join(", "))))?;
+            validate(param, value).map_err(Error::Input)?;
+            res.push((name.to_string(), value.to_string()));
+        }
+        Ok(Params(res))
+    }
+
+    // The value given for the parameter, or its default. The last assignment wins.
+    pub fn get<T: FromStr>(&self, param: &Param) -> Result<T, Error> where T::Err: Display {
+        let value = self.0.iter()
+            .rev()
+            .find(|(name, _)| name == param.name)
+            .map_or(param.default, |(_, value)| value.as_str());
+        validate(param, value).map_err(Error::Input)?;
+        value.parse().map_err(|e| Error::Input(invalid(param, value, e)))
+    }
+}
+
+fn validate(param: &Param, value: &str) -> Result<(), String> {
+    param.kind.check(value).map_err(|e| invalid(param, value, e))
+}
+
+fn invalid(param: &Param, value: &str, reason: impl Display) -> String {
+    format!("Invalid value '{}' for parameter {}: {}", value, param.name, reason)
+}
+
+// Every puzzle's parameters with their defaults, for --help
+pub fn help() -> String {
+    let mut res = String::from("PUZZLE PARAMETERS (--param name=value):");
+    for solution in solution::all() {
+        for (i, param) in solution.params().iter().enumerate() {
+            let id = if i == 0 { solution.id() } else { String::new() };
+            let setting = format!("{}={}", param.name, param.default);
+            res.push_str(&format!("\n    {:<5}  {:<24}  {}", id, setting, param.help));
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SIZE: Param = Param { name: "size", default: "10", kind: Kind::Positive, help: "How big" };
+
+    fn error<T>(reason: &str) -> Result<T, Error> {
+        Err(Error::Input(reason.to_string()))
+    }
+
+    #[test]
+    fn values() {
+        let params = |pairs: &[(&str, &str)]| Params(pairs.iter().map(|&(name, value)| (name.to_string(), value.to_string())).collect());
+        assert_eq!(Ok(10), params(&[]).get::<u32>(&SIZE));
+        assert_eq!(Ok(3), params(&[("other", "5"), ("size", "3")]).get::<u32>(&SIZE));
+        assert_eq!(Ok(4), params(&[("size", "3"), ("size", "4")]).get::<u32>(&SIZE));
+        assert_eq!(error("Invalid value 'big' for parameter size: invalid digit found in string"), params(&[("size", "big")]).get::<u32>(&SIZE));
+        assert_eq!(error("Invalid value '0' for parameter size: must be at least 1"), params(&[("size", "0")]).get::<u32>(&SIZE));
+        assert_eq!(error("Invalid value '-1' for parameter size: must be at least 1"), params(&[("size", "-1")]).get::<i64>(&SIZE));
+        assert_eq!(error("Invalid value '300' for parameter size: number too large to fit in target type"), params(&[("size", "300")]).get::<u8>(&SIZE));
+    }
+
+    #[test]
+    fn checks() {
+        let day8 = solution::find("8-1").unwrap();
+        let pairs = vec![("width".to_string(), "3".to_string()), ("height".to_string(), "2".to_string())];
+        assert_eq!(Ok(Params(pairs)), Params::check(day8, &["width=3", "height = 2"]));
+        assert_eq!(error("8-1 has no parameter 'depth', it takes width, height"), Params::check(day8, &["depth=3"]));
+        assert_eq!(error("Expected name=value, not 'width'"), Params::check(day8, &["width"]));
+        assert_eq!(error("Invalid value '0' for parameter width: must be at least 1"), Params::check(day8, &["width=0"]));
+        assert_eq!(error("Invalid value 'x' for parameter height: invalid digit found in string"), Params::check(day8, &["height = x"]));
+        assert_eq!(error("1-1 doesn't take any parameters"), Params::check(solution::find("1-1").unwrap(), &["x=1"]));
+        for solution in solution::all() {
+            for param in solution.params() {
+                assert_eq!(Ok(()), validate(param, param.default), "{} {}", solution.id(), param.name);
+            }
+        }
+        assert!(help().contains("\n    24-2   minutes=200"), "{}", help());
+    }
+}
@@ -0,0 +1,86 @@
+// Just enough PNG to write 8 bit grayscale images. The image data is stored without compression.
+pub fn grayscale(width: usize, height: usize, value: impl Fn(usize, usize) -> u8) -> Vec<u8> {
+    let mut raw = Vec::with_capacity((width + 1) * height);
+    for y in 0..height {
+        raw.push(0); // no filter
+        for x in 0..width {
+            raw.push(value(x, y));
+        }
+    }
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 0, 0, 0, 0]); // bit depth, color type, compression, filter, interlace
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut res = vec![0x78, 0x01];
+    let mut blocks = data.chunks(0xffff).peekable();
+    if blocks.peek().is_none() {
+        res.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        res.push(if blocks.peek().is_none() { 1 } else { 0 });
+        let len = block.len() as u16;
+        res.extend_from_slice(&len.to_le_bytes());
+        res.extend_from_slice(&(!len).to_le_bytes());
+        res.extend_from_slice(block);
+    }
+    res.extend_from_slice(&adler32(data).to_be_bytes());
+    res
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), &x| {
+        let a = (a + x as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checksums() {
+        assert_eq!(0xae42_6082, crc32(b"IEND"));
+        assert_eq!(0x11e6_0398, adler32(b"Wikipedia"));
+    }
+
+    #[test]
+    fn image() {
+        let png = grayscale(2, 1, |x, _| x as u8 * 255);
+        assert_eq!(b"\x89PNG\r\n\x1a\n", &png[..8]);
+        // IHDR length and type, then the dimensions
+        assert_eq!(&[0, 0, 0, 13, b'I', b'H', b'D', b'R', 0, 0, 0, 2, 0, 0, 0, 1], &png[8..24]);
+        assert_eq!(b"IEND", &png[png.len() - 8..png.len() - 4]);
+    }
+}
@@ -0,0 +1,36 @@
+// Plain portable bitmap. Set pixels are drawn black.
+pub fn pbm(width: usize, height: usize, set: impl Fn(usize, usize) -> bool) -> String {
+    let mut s = format!("P1\n{} {}\n", width, height);
+    for y in 0..height {
+        let row: Vec<&str> = (0..width).map(|x| if set(x, y) { "1" } else { "0" }).collect();
+        s.push_str(&row.join(" "));
+        s.push('\n');
+    }
+    s
+}
+
+// Plain portable graymap. 0 is black and max_value is white.
+pub fn pgm(width: usize, height: usize, max_value: u16, value: impl Fn(usize, usize) -> u16) -> String {
+    let mut s = format!("P2\n{} {}\n{}\n", width, height, max_value);
+    for y in 0..height {
+        let row: Vec<String> = (0..width).map(|x| value(x, y).min(max_value).to_string()).collect();
+        s.push_str(&row.join(" "));
+        s.push('\n');
+    }
+    s
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bitmap() {
+        assert_eq!("P1\n3 2\n1 0 0\n0 1 0\n", pbm(3, 2, |x, y| x == y));
+    }
+
+    #[test]
+    fn graymap() {
+        assert_eq!("P2\n2 2\n3\n0 1\n1 2\n", pgm(2, 2, 3, |x, y| (x + y) as u16));
+    }
+}
@@ -0,0 +1,118 @@
+use crate::config::Config;
+use crate::parallel;
+use crate::solution::{self, Solution, Source};
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub const ANSWERS: &str = "answers.txt";
+
+#[derive(Debug, Eq, PartialEq)]
+enum Verdict {
+    Pass,
+    Fail(String),
+    // Nothing to compare against
+    New,
+    Error(String),
+}
+
+// One puzzle per line: its id, whitespace, then the answer as printed, with images as <WxH image>.
+// Lines starting with # are comments.
+fn parse_answers(s: &str) -> Result<HashMap<String, String>, String> {
+    s.lines()
+        .enumerate()
+        .map(|(i, line)| (i, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| {
+            let (id, answer) = line.split_once(char::is_whitespace)
+                .ok_or_else(|| format!("Line {}: expected an id and an answer in '{}'", i + 1, line))?;
+            Ok((id.to_string(), answer.trim().to_string()))
+        })
+        .collect()
+}
+
+fn judge(result: Result<String, String>, expected: Option<&String>) -> Verdict {
+    match (result, expected) {
+        (Err(e), _) => Verdict::Error(e),
+        (Ok(answer), Some(expected)) if &answer == expected => Verdict::Pass,
+        (Ok(_), Some(expected)) => Verdict::Fail(expected.clone()),
+        (Ok(_), None) => Verdict::New,
+    }
+}
+
+fn run_one(solution: &dyn Solution, config: &Config) -> (Result<String, String>, Duration) {
+    let start = Instant::now();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| solution::execute(solution, &Source::InputDir, config)))
+        .map(|result| result.map(|answer| answer.summary()).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| Err(match e.downcast_ref::<String>() {
+            Some(message) => format!("panicked: {}", message),
+            None => format!("panicked: {}", e.downcast_ref::<&str>().unwrap_or(&"?")),
+        }));
+    (result, start.elapsed())
+}
+
+// Runs every registered puzzle, spread over the Config's jobs, printing a row for each in order as
+// soon as it and the ones before it have finished. Returns false if any answer was wrong or a puzzle
+// failed to run.
+pub fn run(answers_path: &Path, config: &Config) -> bool {
+    let answers = match std::fs::read_to_string(answers_path) {
+        Ok(s) => match parse_answers(&s) {
+            Ok(answers) => answers,
+            Err(e) => {
+                println!("{}: {}", answers_path.display(), e);
+                return false
+            }
+        },
+        Err(e) => {
+            log::warn!("No answers to check against in {}: {}", answers_path.display(), e);
+            HashMap::new()
+        }
+    };
+
+    println!("{:>5}  {:<4}  {:>10}  answer", "day", "", "time");
+    let start = Instant::now();
+    let mut total = Duration::default();
+    let mut failed = 0;
+    let solutions = solution::all();
+    parallel::for_each_ordered(config.jobs(), solutions.clone(), |it| run_one(it, config), |i, (result, time)| {
+        let solution = solutions[i];
+        total += time;
+        let answer = result.clone().unwrap_or_default();
+        let (status, note) = match judge(result, answers.get(&solution.id())) {
+            Verdict::Pass => ("ok", String::new()),
+            Verdict::New => ("new", String::new()),
+            Verdict::Fail(expected) => ("FAIL", format!(" (expected {})", expected)),
+            Verdict::Error(e) => ("ERR", e),
+        };
+        if status == "FAIL" || status == "ERR" {
+            failed += 1;
+        }
+        println!("{:>5}  {:<4}  {:>8.1}ms  {}{}", solution.id(), status, time.as_secs_f64() * 1000.0, answer, note);
+    });
+    println!("{} of {} failed in {:.1}s ({:.1}s of work on {} jobs)",
+             failed, solutions.len(), start.elapsed().as_secs_f64(), total.as_secs_f64(), config.jobs());
+    failed == 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn answers() {
+        let answers = parse_answers("# comment\n1-1 3275518\n\n5-1  0,0,11049715\n").unwrap();
+        assert_eq!(2, answers.len());
+        assert_eq!("0,0,11049715", answers["5-1"]);
+        assert!(parse_answers("1-1").is_err());
+    }
+
+    #[test]
+    fn verdicts() {
+        let expected = "42".to_string();
+        assert_eq!(Verdict::Pass, judge(Ok("42".to_string()), Some(&expected)));
+        assert_eq!(Verdict::Fail(expected.clone()), judge(Ok("41".to_string()), Some(&expected)));
+        assert_eq!(Verdict::New, judge(Ok("41".to_string()), None));
+        assert_eq!(Verdict::Error("oops".to_string()), judge(Err("oops".to_string()), Some(&expected)));
+    }
+}
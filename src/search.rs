@@ -0,0 +1,207 @@
+use priority_queue::PriorityQueue;
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+// The result of a search: the cost of every node reached and how it was reached
+pub struct Search<N> {
+    costs: HashMap<N, u32>,
+    prev: HashMap<N, N>,
+    pub goal: Option<N>,
+}
+
+impl<N: Hash + Eq + Clone> Search<N> {
+    fn new() -> Search<N> {
+        Search { costs: HashMap::new(), prev: HashMap::new(), goal: None }
+    }
+
+    pub fn cost(&self, node: &N) -> Option<u32> {
+        self.costs.get(node).copied()
+    }
+
+    pub fn costs(&self) -> &HashMap<N, u32> {
+        &self.costs
+    }
+
+    pub fn max_cost(&self) -> Option<u32> {
+        self.costs.values().max().copied()
+    }
+
+    pub fn goal_cost(&self) -> Option<u32> {
+        self.goal.as_ref().and_then(|goal| self.cost(goal))
+    }
+
+    // The nodes from the start that reached it up to and including node
+    pub fn path(&self, node: &N) -> Option<Vec<N>> {
+        if !self.costs.contains_key(node) {
+            return None
+        }
+        let mut path = vec![node.clone()];
+        let mut current = node;
+        while let Some(p) = self.prev.get(current) {
+            path.push(p.clone());
+            current = p;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    pub fn goal_path(&self) -> Option<Vec<N>> {
+        self.goal.as_ref().and_then(|goal| self.path(goal))
+    }
+}
+
+// Breadth first search visiting everything reachable from start
+pub fn bfs<N, I>(start: N, neighbors: impl FnMut(&N) -> I) -> Search<N>
+    where N: Hash + Eq + Clone, I: IntoIterator<Item = N> {
+    bfs_until(vec![start], neighbors, |_| false)
+}
+
+// Breadth first search from all of starts at once, like a flood fill
+pub fn bfs_multi<N, I>(starts: impl IntoIterator<Item = N>, neighbors: impl FnMut(&N) -> I) -> Search<N>
+    where N: Hash + Eq + Clone, I: IntoIterator<Item = N> {
+    bfs_until(starts, neighbors, |_| false)
+}
+
+// Breadth first search that stops at the first node dequeued that is a goal
+pub fn bfs_until<N, I>(
+    starts: impl IntoIterator<Item = N>,
+    mut neighbors: impl FnMut(&N) -> I,
+    mut goal: impl FnMut(&N) -> bool,
+) -> Search<N>
+    where N: Hash + Eq + Clone, I: IntoIterator<Item = N> {
+    let mut search = Search::new();
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if !search.costs.contains_key(&start) {
+            search.costs.insert(start.clone(), 0);
+            queue.push_back(start);
+        }
+    }
+    while let Some(node) = queue.pop_front() {
+        if goal(&node) {
+            search.goal = Some(node);
+            break
+        }
+        let cost = search.costs[&node] + 1;
+        for next in neighbors(&node) {
+            if !search.costs.contains_key(&next) {
+                search.costs.insert(next.clone(), cost);
+                search.prev.insert(next.clone(), node.clone());
+                queue.push_back(next);
+            }
+        }
+    }
+    search
+}
+
+// Cheapest paths where neighbors gives each next node with the cost of the step to it
+pub fn dijkstra<N, I>(
+    start: N,
+    neighbors: impl FnMut(&N) -> I,
+    goal: impl FnMut(&N) -> bool,
+) -> Search<N>
+    where N: Hash + Eq + Clone, I: IntoIterator<Item = (N, u32)> {
+    astar(start, neighbors, |_| 0, goal)
+}
+
+// Dijkstra guided by a heuristic, which must never overestimate the remaining cost to a goal
+pub fn astar<N, I>(
+    start: N,
+    mut neighbors: impl FnMut(&N) -> I,
+    mut heuristic: impl FnMut(&N) -> u32,
+    mut goal: impl FnMut(&N) -> bool,
+) -> Search<N>
+    where N: Hash + Eq + Clone, I: IntoIterator<Item = (N, u32)> {
+    let mut search = Search::new();
+    let mut queue = PriorityQueue::new();
+    search.costs.insert(start.clone(), 0);
+    let h = heuristic(&start);
+    queue.push(start, Reverse(h));
+    while let Some((node, _)) = queue.pop() {
+        if goal(&node) {
+            search.goal = Some(node);
+            break
+        }
+        let cost = search.costs[&node];
+        for (next, step) in neighbors(&node) {
+            let new_cost = cost + step;
+            if search.cost(&next).is_none_or(|old_cost| new_cost < old_cost) {
+                search.costs.insert(next.clone(), new_cost);
+                search.prev.insert(next.clone(), node.clone());
+                let h = heuristic(&next);
+                queue.push(next, Reverse(new_cost + h));
+            }
+        }
+    }
+    search
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::grid::{self, Grid, Pos};
+
+    const MAZE: &str = "#########
+#S..#...#
+#.#.#.#.#
+#.#...#G#
+#########";
+
+    fn open(maze: &Grid<char>) -> impl FnMut(&Pos) -> Vec<Pos> + '_ {
+        move |&pos| grid::neighbors4(pos).iter().copied().filter(|&it| maze.get(it) != Some(&'#')).collect()
+    }
+
+    #[test]
+    fn breadth_first() {
+        let maze = Grid::parse(MAZE, Some).unwrap();
+        let start = maze.find(|&c| c == 'S').unwrap();
+        let search = bfs(start, open(&maze));
+        assert_eq!(Some(12), search.cost(&(7, 3)));
+        assert_eq!(Some(12), search.max_cost());
+        let path = search.path(&(7, 3)).unwrap();
+        assert_eq!(13, path.len());
+        assert_eq!((start, (7, 3)), (path[0], path[12]));
+        assert!(path.windows(2).all(|it| grid::neighbors4(it[0]).contains(&it[1])));
+        assert_eq!(None, search.path(&(0, 0)));
+
+        let search = bfs_until(vec![start], open(&maze), |&pos| maze[pos] == 'G');
+        assert_eq!(Some((7, 3)), search.goal);
+        assert_eq!(Some(12), search.goal_cost());
+    }
+
+    #[test]
+    fn flood_fill() {
+        let maze = Grid::parse(MAZE, Some).unwrap();
+        let search = bfs_multi(vec![(1, 1), (7, 3)], open(&maze));
+        assert_eq!(Some(6), search.max_cost());
+        assert_eq!(Some(vec![(7, 3), (7, 2), (7, 1)]), search.path(&(7, 1)));
+    }
+
+    #[test]
+    fn weighted() {
+        // a->b->c is cheaper than a->c and a->d->c is the cheapest
+        let edges = |&n: &char| -> Vec<(char, u32)> {
+            match n {
+                'a' => vec![('b', 2), ('c', 5), ('d', 1)],
+                'b' => vec![('c', 2)],
+                'd' => vec![('c', 2)],
+                _ => vec![],
+            }
+        };
+        let search = dijkstra('a', edges, |&n| n == 'c');
+        assert_eq!(Some(3), search.goal_cost());
+        assert_eq!(Some(vec!['a', 'd', 'c']), search.goal_path());
+
+        let maze = Grid::parse(MAZE, Some).unwrap();
+        let goal = (7, 3);
+        let search = astar(
+            (1, 1),
+            |&pos| open(&maze)(&pos).into_iter().map(|it| (it, 1)).collect::<Vec<_>>(),
+            |&(x, y)| ((goal.0 - x).abs() + (goal.1 - y).abs()) as u32,
+            |&pos| pos == goal,
+        );
+        assert_eq!(Some(12), search.goal_cost());
+        assert_eq!(13, search.goal_path().unwrap().len());
+    }
+}
@@ -0,0 +1,235 @@
+use crate::answer::Answer;
+use crate::config::Config;
+use crate::error::{Error, ParseError};
+use crate::params::Param;
+use std::io::Read;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+const DEFAULT_INPUT_DIR: &str = "input";
+
+// One part of one day's puzzle. Days register theirs with the solution! macro, so nothing else has to
+// know about them.
+pub trait Solution: Sync {
+    fn day(&self) -> u32;
+    fn part(&self) -> u32;
+    fn title(&self) -> &str;
+    // Relative to the input directory
+    fn input_file(&self) -> &str;
+    fn run(&self, input: &str, config: &Config) -> Result<Answer, Error>;
+    // What can be changed with --param
+    fn params(&self) -> &'static [Param] {
+        &[]
+    }
+
+    fn id(&self) -> String {
+        format!("{}-{}", self.day(), self.part())
+    }
+}
+
+inventory::collect!(&'static dyn Solution);
+
+// What solve can return: an answer, or a Result of one for puzzles whose input can turn out to be
+// unusable in ways that parsing a line at a time can't catch
+pub trait Outcome {
+    fn into_answer(self) -> Result<Answer, Error>;
+}
+
+macro_rules! outcome {
+    ($($t:ty),*) => {
+        $(impl Outcome for $t {
+            fn into_answer(self) -> Result<Answer, Error> {
+                Ok(self.into())
+            }
+        })*
+    };
+}
+
+outcome!(Answer, i32, i64, u32, u64, usize, String, &'static str, Vec<i64>, (i64, i64));
+
+impl<A: Into<Answer>> Outcome for Result<A, Error> {
+    fn into_answer(self) -> Result<Answer, Error> {
+        self.map(Into::into)
+    }
+}
+
+// Parses each line of the input as a T and hands them all to solve
+pub struct Puzzle<T, R> {
+    pub day: u32,
+    pub part: u32,
+    pub title: &'static str,
+    pub input: &'static str,
+    pub solve: fn(Vec<T>, &Config) -> R,
+    pub params: &'static [Param],
+}
+
+impl<T: FromStr, R: Outcome> Solution for Puzzle<T, R> where T::Err: Into<ParseError> {
+    fn day(&self) -> u32 {
+        self.day
+    }
+
+    fn part(&self) -> u32 {
+        self.part
+    }
+
+    fn title(&self) -> &str {
+        self.title
+    }
+
+    fn input_file(&self) -> &str {
+        self.input
+    }
+
+    fn run(&self, input: &str, config: &Config) -> Result<Answer, Error> {
+        (self.solve)(crate::parse_input(input)?, config).into_answer()
+    }
+
+    fn params(&self) -> &'static [Param] {
+        self.params
+    }
+}
+
+// solution!(day, part, title, input file, solve) where solve takes a Vec of parsed lines. Solvers that
+// need the Config, for their parameters or anything else, take it as well and follow solve with the
+// parameters they read, &[] for none.
+macro_rules! solution {
+    ($day:expr, $part:expr, $title:expr, $input:expr, $solve:expr) => {
+        solution!($day, $part, $title, $input, |input, _| $solve(input), &[]);
+    };
+    ($day:expr, $part:expr, $title:expr, $input:expr, $solve:expr, $params:expr) => {
+        inventory::submit! {
+            &crate::solution::Puzzle { day: $day, part: $part, title: $title, input: $input, solve: $solve, params: $params }
+                as &dyn crate::solution::Solution
+        }
+    };
+}
+
+pub enum Source {
+    // The solution's input file in the Config's input directory
+    InputDir,
+    File(PathBuf),
+    Stdin,
+}
+
+impl Source {
+    // - is stdin
+    pub fn from_arg(arg: &str) -> Source {
+        if arg == "-" { Source::Stdin } else { Source::File(PathBuf::from(arg)) }
+    }
+}
+
+fn input_path(solution: &dyn Solution, config: &Config) -> PathBuf {
+    let dir = config.input_dir.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_INPUT_DIR));
+    dir.join(solution.input_file())
+}
+
+pub fn read_input(solution: &dyn Solution, source: &Source, config: &Config) -> Result<String, Error> {
+    match source {
+        Source::InputDir => {
+            let path = input_path(solution, config);
+            std::fs::read_to_string(&path).map_err(|e| Error::Input(format!(
+                "Could not read the input for {} from {}: {}. Save the puzzle input there, pass --input-dir with the directory holding it, or pass --input",
+                solution.id(), path.display(), e)))
+        }
+        Source::File(path) => std::fs::read_to_string(path)
+            .map_err(|e| Error::Input(format!("Could not read the input for {} from {}: {}", solution.id(), path.display(), e))),
+        Source::Stdin => {
+            let mut s = String::new();
+            std::io::stdin().read_to_string(&mut s).map_err(|e| Error::Input(format!("Could not read the input from stdin: {}", e)))?;
+            Ok(s)
+        }
+    }
+}
+
+pub fn execute(solution: &dyn Solution, source: &Source, config: &Config) -> Result<Answer, Error> {
+    let name = match source {
+        Source::InputDir => input_path(solution, config).display().to_string(),
+        Source::File(path) => path.display().to_string(),
+        Source::Stdin => "<stdin>".to_string(),
+    };
+    solution.run(&read_input(solution, source, config)?, config).map_err(|e| e.in_file(&name))
+}
+
+// Ordered by day and part
+pub fn all() -> Vec<&'static dyn Solution> {
+    let mut res: Vec<&'static dyn Solution> = inventory::iter::<&'static dyn Solution>.into_iter().copied().collect();
+    res.sort_by_key(|it| (it.day(), it.part()));
+    res
+}
+
+pub fn list() -> String {
+    all().iter()
+        .map(|it| format!("{:>5}  {}", it.id(), it.title()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn find(id: &str) -> Result<&'static dyn Solution, String> {
+    let solutions = all();
+    if let Some(&solution) = solutions.iter().find(|it| it.id() == id) {
+        return Ok(solution)
+    }
+    let suggestions = suggest(id, &solutions);
+    if suggestions.is_empty() {
+        Err(format!("No puzzle '{}', try list to see them all", id))
+    } else {
+        Err(format!("No puzzle '{}', did you mean {}?", id, suggestions.join(" or ")))
+    }
+}
+
+// The other parts of the same day, or else the nearest days one edit away
+fn suggest(id: &str, solutions: &[&dyn Solution]) -> Vec<String> {
+    let day = id.split('-').next().unwrap_or(id);
+    let same_day: Vec<String> = solutions.iter().filter(|it| it.day().to_string() == day).map(|it| it.id()).collect();
+    if !same_day.is_empty() {
+        return same_day
+    }
+    let day: i64 = day.parse().unwrap_or(0);
+    let mut close: Vec<&dyn Solution> = solutions.iter().copied().filter(|it| edit_distance(id, &it.id()) <= 1).collect();
+    close.sort_by_key(|it| ((it.day() as i64 - day).abs(), it.part()));
+    close.iter().take(3).map(|it| it.id()).collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            row.push((prev[j] + cost).min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn registry() {
+        let ids: Vec<String> = all().iter().map(|it| it.id()).collect();
+        assert_eq!(50, ids.len());
+        assert_eq!("1-1", ids[0]);
+        assert_eq!("25-2", ids[49]);
+        assert_eq!("Crossed Wires", find("3-2").unwrap().title());
+        assert_eq!("18-2.txt", find("18-2").unwrap().input_file());
+    }
+
+    #[test]
+    fn missing_input() {
+        let e = read_input(find("1-1").unwrap(), &Source::from_arg("no/such/file.txt"), &Config::default()).err().unwrap().to_string();
+        assert!(e.starts_with("Could not read the input for 1-1 from no/such/file.txt"), "{}", e);
+    }
+
+    #[test]
+    fn suggestions() {
+        assert_eq!("No puzzle '7-3', did you mean 7-1 or 7-2?", find("7-3").err().unwrap());
+        assert_eq!("No puzzle '26-1', did you mean 25-1 or 24-1 or 23-1?", find("26-1").err().unwrap());
+        assert_eq!("No puzzle '12', did you mean 12-1 or 12-2?", find("12").err().unwrap());
+        assert_eq!("No puzzle 'foo', try list to see them all", find("foo").err().unwrap());
+        assert_eq!(1, edit_distance("12", "1-2"));
+    }
+}
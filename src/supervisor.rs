@@ -0,0 +1,211 @@
+use crate::intcode::{Computer, ComputerState, Value};
+use std::fmt;
+use std::rc::Rc;
+
+// Runs a set of computers connected by streams in rounds and works out when they have stopped
+// making progress. Whatever moves values between the streams should do so before asking for the
+// status.
+pub struct Supervisor {
+    names: Vec<String>,
+    computers: Vec<Computer>,
+    machines: Vec<Machine>,
+    poll: Option<Value>,
+}
+
+#[derive(Default)]
+struct Machine {
+    halted: bool,
+    progressed: bool,
+    poll_pending: bool,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Status {
+    Running,
+    Halted,
+    // Every computer is polling an empty input
+    Idle,
+    Deadlocked(Vec<Blocked>),
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Blocked {
+    pub machine: String,
+    pub stream: String,
+}
+
+impl fmt::Display for Blocked {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} is waiting on {}", self.machine, self.stream)
+    }
+}
+
+impl Default for Supervisor {
+    fn default() -> Supervisor {
+        Supervisor::new()
+    }
+}
+
+impl Supervisor {
+    // Computers block when their input is empty
+    pub fn new() -> Supervisor {
+        Supervisor {
+            names: Vec::new(),
+            computers: Vec::new(),
+            machines: Vec::new(),
+            poll: None,
+        }
+    }
+
+    // Computers are given the poll value whenever their input is empty, so they never block
+    pub fn polling(poll: Value) -> Supervisor {
+        Supervisor {
+            poll: Some(poll),
+            ..Supervisor::new()
+        }
+    }
+
+    pub fn add(&mut self, name: &str, computer: Computer) {
+        assert!(computer.input().is_some(), "{} needs an input stream", name);
+        self.names.push(name.to_string());
+        self.computers.push(computer);
+        self.machines.push(Machine::default());
+    }
+
+    pub fn computers(&self) -> &[Computer] {
+        &self.computers
+    }
+
+    // Executes every computer that hasn't halted until it halts or needs input
+    pub fn round(&mut self) {
+        for ((name, comp), machine) in self.names.iter().zip(self.computers.iter_mut()).zip(self.machines.iter_mut()) {
+            if machine.halted {
+                continue
+            }
+            let input = comp.input().unwrap();
+            // Counted rather than measured, as the output can be wired back into the input
+            let output_before = comp.output().map(|it| it.borrow().writes()).unwrap_or(0);
+            let input_before = input.borrow().reads();
+            log::debug!("Executing {}", name);
+            let state = comp.execute();
+            let consumed = input.borrow().reads() - input_before;
+            let polls = if machine.poll_pending && consumed > 0 { 1 } else { 0 };
+            let produced = comp.output().map(|it| it.borrow().writes()).unwrap_or(0) > output_before;
+            machine.halted = state == ComputerState::Halted;
+            machine.progressed = consumed > polls || produced || machine.halted;
+            machine.poll_pending = machine.poll_pending && consumed == 0;
+            if let (ComputerState::WaitingOnInput, Some(poll), false) = (state, self.poll, machine.poll_pending) {
+                if input.borrow().is_empty() {
+                    input.borrow_mut().write(poll);
+                    machine.poll_pending = true;
+                }
+            }
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        if self.machines.iter().all(|it| it.halted) {
+            return Status::Halted
+        }
+        let waiting: Vec<usize> = (0..self.machines.len()).filter(|&i| !self.machines[i].halted).collect();
+        let in_flight = waiting.iter().any(|&i| {
+            let polls = if self.machines[i].poll_pending { 1 } else { 0 };
+            self.computers[i].input().unwrap().borrow().len() > polls
+        });
+        if in_flight || self.machines.iter().any(|it| it.progressed) {
+            Status::Running
+        } else if self.poll.is_some() {
+            Status::Idle
+        } else {
+            Status::Deadlocked(waiting.into_iter()
+                .map(|i| Blocked { machine: self.names[i].clone(), stream: self.stream_name(i) })
+                .collect())
+        }
+    }
+
+    // Streams are named after the first computer that reads them
+    fn stream_name(&self, i: usize) -> String {
+        let input = self.computers[i].input().unwrap();
+        let owner = self.computers.iter()
+            .position(|it| it.input().map(|s| Rc::ptr_eq(&s, &input)).unwrap_or(false))
+            .unwrap_or(i);
+        format!("{}.in", self.names[owner])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::{parse_program, Stream};
+
+    // Reads one value, writes it back out and halts
+    const ECHO_ONCE: &str = "3,5,4,5,99,0";
+    // Reads one value, writes it out twice and halts
+    const ECHO_TWICE: &str = "3,7,4,7,4,7,99,0";
+    // Echoes forever, ignoring -1
+    const ECHO: &str = "3,20,1008,20,-1,21,1005,21,0,4,20,1105,1,0";
+
+    fn pair(program: &str, supervisor: &mut Supervisor) {
+        let a_in = Stream::new_wrapped();
+        let b_in = Stream::new_wrapped();
+        let mut a = Computer::new(parse_program(program).unwrap());
+        a.set_input(Some(Rc::clone(&a_in)));
+        a.set_output(Some(Rc::clone(&b_in)));
+        let mut b = Computer::new(parse_program(program).unwrap());
+        b.set_input(Some(b_in));
+        b.set_output(Some(a_in));
+        supervisor.add("A", a);
+        supervisor.add("B", b);
+    }
+
+    #[test]
+    fn deadlock() {
+        let mut supervisor = Supervisor::new();
+        pair(ECHO_ONCE, &mut supervisor);
+        supervisor.round();
+        let expected = vec![
+            Blocked { machine: "A".to_string(), stream: "A.in".to_string() },
+            Blocked { machine: "B".to_string(), stream: "B.in".to_string() },
+        ];
+        assert_eq!(Status::Deadlocked(expected), supervisor.status());
+    }
+
+    #[test]
+    fn halt() {
+        let mut supervisor = Supervisor::new();
+        pair(ECHO_ONCE, &mut supervisor);
+        supervisor.computers()[0].input().unwrap().borrow_mut().write(7);
+        supervisor.round();
+        assert_eq!(Status::Halted, supervisor.status());
+    }
+
+    #[test]
+    fn feedback() {
+        // Its output grows its own input while it runs
+        let mut supervisor = Supervisor::new();
+        let stream = Stream::new_wrapped();
+        stream.borrow_mut().write(7);
+        let mut comp = Computer::new(parse_program(ECHO_TWICE).unwrap());
+        comp.set_input(Some(Rc::clone(&stream)));
+        comp.set_output(Some(Rc::clone(&stream)));
+        supervisor.add("A", comp);
+        supervisor.round();
+        assert_eq!(Status::Halted, supervisor.status());
+        assert_eq!(vec![7, 7], stream.borrow_mut().read_all());
+    }
+
+    #[test]
+    fn idle() {
+        let mut supervisor = Supervisor::polling(-1);
+        pair(ECHO, &mut supervisor);
+        supervisor.round();
+        assert_eq!(Status::Idle, supervisor.status());
+        supervisor.computers()[0].input().unwrap().borrow_mut().write(7);
+        assert_eq!(Status::Running, supervisor.status());
+        // The value bounces back and forth forever
+        for _ in 0..5 {
+            supervisor.round();
+            assert_eq!(Status::Running, supervisor.status());
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use crate::intcode::{Program, Value};
+use std::collections::BTreeMap;
+use std::ops::{Add, Mul, Range};
+
+// A polynomial over symbols x0, x1, ... keyed by monomial. A monomial lists the symbol of each factor,
+// sorted and with repeats, so [0, 0, 1] is x0^2*x1 and [] is the constant term.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Expr {
+    terms: BTreeMap<Vec<usize>, Value>,
+}
+
+impl Expr {
+    pub fn constant(c: Value) -> Expr {
+        Expr::from_terms(vec![(Vec::new(), c)])
+    }
+
+    pub fn symbol(i: usize) -> Expr {
+        Expr::from_terms(vec![(vec![i], 1)])
+    }
+
+    fn from_terms(terms: impl IntoIterator<Item=(Vec<usize>, Value)>) -> Expr {
+        let mut res = BTreeMap::new();
+        for (mut monomial, c) in terms {
+            monomial.sort_unstable();
+            *res.entry(monomial).or_insert(0) += c;
+        }
+        res.retain(|_, c| *c != 0);
+        Expr { terms: res }
+    }
+
+    pub fn as_constant(&self) -> Option<Value> {
+        match self.terms.iter().next() {
+            None => Some(0),
+            Some((monomial, &c)) if monomial.is_empty() => Some(c),
+            _ => None,
+        }
+    }
+
+    pub fn substitute(&self, symbol: usize, value: Value) -> Expr {
+        Expr::from_terms(self.terms.iter().map(|(monomial, &c)| {
+            let rest: Vec<usize> = monomial.iter().copied().filter(|&it| it != symbol).collect();
+            let power = (monomial.len() - rest.len()) as u32;
+            (rest, c * value.pow(power))
+        }))
+    }
+
+    fn degree_in(&self, symbol: usize) -> usize {
+        self.terms.keys().map(|monomial| monomial.iter().filter(|&&it| it == symbol).count()).max().unwrap_or(0)
+    }
+
+    fn coefficient(&self, monomial: &[usize]) -> Value {
+        self.terms.get(monomial).copied().unwrap_or(0)
+    }
+
+    // Highest degree terms first, e.g. "5*noun*verb + verb + 12"
+    pub fn format(&self, names: &[&str]) -> String {
+        if self.terms.is_empty() {
+            return "0".to_string()
+        }
+        let mut terms: Vec<(&Vec<usize>, &Value)> = self.terms.iter().collect();
+        terms.sort_by_key(|(monomial, _)| std::cmp::Reverse(monomial.len()));
+        terms.into_iter()
+            .map(|(monomial, &c)| {
+                let mut factors: Vec<String> = monomial.iter().map(|&i| names.get(i).map_or(format!("x{}", i), |it| it.to_string())).collect();
+                if c != 1 || factors.is_empty() {
+                    factors.insert(0, c.to_string());
+                }
+                factors.join("*")
+            })
+            .collect::<Vec<_>>()
+            .join(" + ")
+    }
+}
+
+impl Add for &Expr {
+    type Output = Expr;
+
+    fn add(self, rhs: &Expr) -> Expr {
+        Expr::from_terms(self.terms.iter().chain(&rhs.terms).map(|(m, &c)| (m.clone(), c)))
+    }
+}
+
+impl Mul for &Expr {
+    type Output = Expr;
+
+    fn mul(self, rhs: &Expr) -> Expr {
+        Expr::from_terms(self.terms.iter().flat_map(|(m1, &c1)| rhs.terms.iter().map(move |(m2, &c2)| {
+            ([m1.as_slice(), m2.as_slice()].concat(), c1 * c2)
+        })))
+    }
+}
+
+// Addresses beyond this are treated as a program gone wrong rather than sparse memory
+const MAX_MEMORY: usize = 1 << 20;
+
+// Runs the program with symbol i in cell symbols[i], tracking every cell as a polynomial of the
+// symbols. Cells read through an address that depends on the symbols are unknown (None). Fails if
+// the control flow or a write address depends on the symbols, or if the program uses I/O.
+pub fn execute(program: &Program, symbols: &[usize], max_steps: usize) -> Result<Vec<Option<Expr>>, String> {
+    let mut memory: Vec<Option<Expr>> = program.iter().map(|&it| Some(Expr::constant(it))).collect();
+    for (i, &cell) in symbols.iter().enumerate() {
+        if cell >= memory.len() {
+            return Err(format!("Symbol cell {} is outside the program", cell))
+        }
+        memory[cell] = Some(Expr::symbol(i));
+    }
+    let mut machine = Machine { memory, ip: 0, rel_base: 0 };
+    for _ in 0..max_steps {
+        if !machine.step()? {
+            return Ok(machine.memory)
+        }
+    }
+    Err(format!("Did not halt within {} steps", max_steps))
+}
+
+struct Machine {
+    memory: Vec<Option<Expr>>,
+    ip: usize,
+    rel_base: Value,
+}
+
+impl Machine {
+    // Returns false once halted
+    fn step(&mut self) -> Result<bool, String> {
+        let ip = self.ip;
+        let instr = self.concrete(ip)?;
+        let mode = |place: Value| instr / place % 10;
+        match instr % 100 {
+            op @ 1 | op @ 2 | op @ 7 | op @ 8 => {
+                let a = self.read(ip + 1, mode(100))?;
+                let b = self.read(ip + 2, mode(1000))?;
+                let res = match (op, a, b) {
+                    (1, Some(a), Some(b)) => Some(&a + &b),
+                    (2, Some(a), Some(b)) => Some(&a * &b),
+                    (7, Some(a), Some(b)) => a.as_constant().zip(b.as_constant()).map(|(a, b)| Expr::constant((a < b) as Value)),
+                    (8, Some(a), Some(b)) => a.as_constant().zip(b.as_constant()).map(|(a, b)| Expr::constant((a == b) as Value)),
+                    _ => None,
+                };
+                self.write(ip + 3, mode(10000), res)?;
+                self.ip += 4;
+            }
+            3 | 4 => return Err(format!("Input/output at {} is not supported", ip)),
+            op @ 5 | op @ 6 => {
+                let a = self.read_concrete(ip + 1, mode(100))?;
+                let t = self.read_concrete(ip + 2, mode(1000))?;
+                if (a != 0) == (op == 5) {
+                    self.ip = t as usize;
+                } else {
+                    self.ip += 3;
+                }
+            }
+            9 => {
+                self.rel_base += self.read_concrete(ip + 1, mode(100))?;
+                self.ip += 2;
+            }
+            99 => return Ok(false),
+            op => return Err(format!("Unrecognized opcode: {} @ ip {}", op, ip)),
+        }
+        Ok(true)
+    }
+
+    fn concrete(&self, addr: usize) -> Result<Value, String> {
+        match self.memory.get(addr) {
+            None => Ok(0),
+            Some(cell) => cell.as_ref()
+                .and_then(Expr::as_constant)
+                .ok_or_else(|| format!("Cell {} depends on the symbols", addr)),
+        }
+    }
+
+    fn address(&self, at: usize, mode: Value) -> Result<Option<usize>, String> {
+        let param = match self.memory.get(at) {
+            None => Some(0),
+            Some(cell) => cell.as_ref().and_then(Expr::as_constant),
+        };
+        let addr = match mode {
+            0 => param,
+            2 => param.map(|it| it + self.rel_base),
+            _ => return Err(format!("Unsupported parameter mode: {}", mode)),
+        };
+        match addr {
+            Some(addr) if !(0..MAX_MEMORY as Value).contains(&addr) =>
+                Err(format!("Address {} at {} is out of range", addr, self.ip)),
+            addr => Ok(addr.map(|it| it as usize)),
+        }
+    }
+
+    fn read(&self, at: usize, mode: Value) -> Result<Option<Expr>, String> {
+        if mode == 1 {
+            return Ok(self.memory.get(at).cloned().unwrap_or_else(|| Some(Expr::constant(0))))
+        }
+        Ok(match self.address(at, mode)? {
+            Some(addr) => self.memory.get(addr).cloned().unwrap_or_else(|| Some(Expr::constant(0))),
+            None => None,
+        })
+    }
+
+    fn read_concrete(&self, at: usize, mode: Value) -> Result<Value, String> {
+        self.read(at, mode)?
+            .and_then(|it| it.as_constant())
+            .ok_or_else(|| format!("Control flow at {} depends on the symbols", self.ip))
+    }
+
+    fn write(&mut self, at: usize, mode: Value, value: Option<Expr>) -> Result<(), String> {
+        let addr = self.address(at, mode)?
+            .ok_or_else(|| format!("Write address at {} depends on the symbols", self.ip))?;
+        if addr >= self.memory.len() {
+            self.memory.resize(addr + 1, Some(Expr::constant(0)));
+        }
+        self.memory[addr] = value;
+        Ok(())
+    }
+}
+
+// Finds the first assignment of the symbols, in order of their domains, where expr equals target.
+// Once only one symbol is left and expr is linear in it, that symbol is solved for directly.
+pub fn solve(expr: &Expr, target: Value, domains: &[Range<Value>]) -> Option<Vec<Value>> {
+    solve_from(expr, target, domains, 0)
+}
+
+fn solve_from(expr: &Expr, target: Value, domains: &[Range<Value>], symbol: usize) -> Option<Vec<Value>> {
+    let domain = match domains.get(symbol) {
+        Some(domain) => domain.clone(),
+        None => return if expr.as_constant() == Some(target) { Some(Vec::new()) } else { None },
+    };
+    if symbol + 1 == domains.len() && expr.degree_in(symbol) <= 1 {
+        let a = expr.coefficient(&[symbol]);
+        let rest = target - expr.coefficient(&[]);
+        let x = match a {
+            0 if rest == 0 => domain.start,
+            0 => return None,
+            _ if rest % a == 0 => rest / a,
+            _ => return None,
+        };
+        return if domain.contains(&x) { Some(vec![x]) } else { None }
+    }
+    domain.into_iter().find_map(|x| {
+        let mut res = solve_from(&expr.substitute(symbol, x), target, domains, symbol + 1)?;
+        res.insert(0, x);
+        Some(res)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::parse_program;
+
+    #[test]
+    fn polynomial() {
+        let x = Expr::symbol(0);
+        let y = Expr::symbol(1);
+        let e = &(&(&x * &y) + &x) + &Expr::constant(3);
+        assert_eq!("x0*x1 + x0 + 3", e.format(&[]));
+        assert_eq!("2*y + 5", e.substitute(0, 2).format(&["x", "y"]));
+        assert_eq!(Some(7), e.substitute(0, 2).substitute(1, 1).as_constant());
+        assert_eq!(Some(0), (&x + &(&Expr::constant(-1) * &x)).as_constant());
+    }
+
+    #[test]
+    fn straight_line() {
+        // [0] = [9] * [10] + [11]
+        let program = parse_program("2,9,10,0,1,0,11,0,99,3,0,0").unwrap();
+        let memory = execute(&program, &[10, 11], 100).unwrap();
+        let result = memory[0].as_ref().unwrap();
+        assert_eq!("3*x0 + x1", result.format(&[]));
+        assert_eq!(Some(vec![2, 8]), solve(result, 14, &[0..10, 0..10]));
+        assert_eq!(None, solve(result, 14, &[0..4, 0..2]));
+    }
+
+    #[test]
+    fn symbolic_addresses() {
+        // Reading through a symbolic address is only a problem if the result is used
+        let program = parse_program("1,1,2,3,1,1,2,0,99").unwrap();
+        let memory = execute(&program, &[1, 2], 100).unwrap();
+        assert_eq!(None, memory[3]);
+        assert_eq!(Some("x0 + x1".to_string()), memory[0].as_ref().map(|it| it.format(&[])));
+        // ...but writing through one is
+        assert!(execute(&parse_program("1,0,0,0,99").unwrap(), &[3], 100).is_err());
+    }
+
+    #[test]
+    fn bad_addresses() {
+        assert!(execute(&parse_program("1,0,0,-1,99").unwrap(), &[], 100).is_err());
+        assert!(execute(&parse_program("1,0,0,1000000000000,99").unwrap(), &[], 100).is_err());
+        assert!(execute(&parse_program("2,-5,0,0,99").unwrap(), &[], 100).is_err());
+    }
+
+    #[test]
+    fn symbolic_control_flow() {
+        let program = parse_program("1005,1,4,99,99").unwrap();
+        assert!(execute(&program, &[1], 100).is_err());
+        assert!(execute(&parse_program("1105,1,0").unwrap(), &[], 100).is_err());
+    }
+}
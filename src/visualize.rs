@@ -0,0 +1,201 @@
+use crate::answer::json_string;
+use crate::config::Config;
+use std::fs;
+use std::io::{stdout, Write};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+// Drawing in the terminal and recording are each switched on in the Config, and frames are ignored
+// when neither is so batch runs stay quiet
+pub const DEFAULT_FPS: f64 = 30.0;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Color {
+    Default,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Cyan,
+    White,
+    Gray,
+}
+
+impl Color {
+    fn sgr(self) -> u8 {
+        match self {
+            Color::Default => 39,
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Cyan => 36,
+            Color::White => 37,
+            Color::Gray => 90,
+        }
+    }
+}
+
+pub trait Frame {
+    // Top to bottom, each cell a character and its colour
+    fn rows(&self) -> Vec<Vec<(char, Color)>>;
+
+    // Shown under the picture, e.g. a score
+    fn caption(&self) -> String {
+        String::new()
+    }
+}
+
+// A frame made from already rendered text, coloured a character at a time
+pub struct Picture {
+    rows: Vec<Vec<(char, Color)>>,
+    caption: String,
+}
+
+impl Picture {
+    pub fn new(text: &str, color: impl Fn(char) -> Color) -> Picture {
+        let rows = text.lines()
+            .map(|line| line.chars().map(|c| (c, color(c))).collect())
+            .collect();
+        Picture { rows, caption: String::new() }
+    }
+
+    pub fn with_caption(mut self, caption: impl Into<String>) -> Picture {
+        self.caption = caption.into();
+        self
+    }
+}
+
+impl Frame for Picture {
+    fn rows(&self) -> Vec<Vec<(char, Color)>> {
+        self.rows.clone()
+    }
+
+    fn caption(&self) -> String {
+        self.caption.clone()
+    }
+}
+
+struct Recording {
+    frames: usize,
+    width: usize,
+    height: usize,
+    events: Vec<(f64, String)>,
+}
+
+static RECORDING: Mutex<Recording> = Mutex::new(Recording { frames: 0, width: 0, height: 0, events: Vec::new() });
+
+pub fn enabled(config: &Config) -> bool {
+    config.visualize || config.record.is_some()
+}
+
+pub fn fps(config: &Config) -> f64 {
+    config.fps.filter(|&it| it > 0.0).unwrap_or(DEFAULT_FPS)
+}
+
+// Draws the frame over the previous one and waits for the next frame's turn
+pub fn show(config: &Config, frame: &dyn Frame) {
+    if !enabled(config) {
+        return
+    }
+    let rows = frame.rows();
+    let caption = frame.caption();
+    let mut recording = RECORDING.lock().unwrap();
+    let mut out = String::from(if recording.frames == 0 { "\x1b[2J\x1b[H" } else { "\x1b[H" });
+    out.push_str(&to_ansi(&rows));
+    if !caption.is_empty() {
+        out.push_str(&caption);
+        out.push_str("\x1b[K\n");
+    }
+    out.push_str("\x1b[J");
+
+    let fps = fps(config);
+    let time = recording.frames as f64 / fps;
+    recording.frames += 1;
+    recording.width = recording.width.max(rows.iter().map(|it| it.len()).max().unwrap_or(0)).max(caption.chars().count());
+    recording.height = recording.height.max(rows.len() + if caption.is_empty() { 0 } else { 1 });
+    if config.record.is_some() {
+        recording.events.push((time, out.replace('\n', "\r\n")));
+    }
+    drop(recording);
+
+    if config.visualize {
+        let mut stdout = stdout();
+        stdout.write_all(out.as_bytes()).and_then(|_| stdout.flush()).unwrap();
+        thread::sleep(Duration::from_secs_f64(1.0 / fps));
+    }
+}
+
+// Only changes colour where it has to, and clears whatever the previous frame left on the line
+fn to_ansi(rows: &[Vec<(char, Color)>]) -> String {
+    let mut res = String::new();
+    for row in rows {
+        let mut current = Color::Default;
+        for &(c, color) in row {
+            if color != current {
+                res.push_str(&format!("\x1b[{}m", color.sgr()));
+                current = color;
+            }
+            res.push(c);
+        }
+        if current != Color::Default {
+            res.push_str("\x1b[0m");
+        }
+        res.push_str("\x1b[K\n");
+    }
+    res
+}
+
+// Writes the frames shown so far to the record file as an asciicast v2 recording
+pub fn finish(config: &Config) {
+    let path = match &config.record {
+        Some(path) => path,
+        None => return
+    };
+    let mut recording = RECORDING.lock().unwrap();
+    if recording.events.is_empty() {
+        log::warn!("Nothing to record, this puzzle doesn't show any frames");
+        return
+    }
+    let contents = to_asciicast(&recording);
+    match fs::write(path, contents) {
+        Ok(()) => log::info!("Recorded {} frames to {}", recording.events.len(), path.display()),
+        Err(e) => log::warn!("Could not record to {}: {}", path.display(), e),
+    }
+    *recording = Recording { frames: 0, width: 0, height: 0, events: Vec::new() };
+}
+
+// {"version": 2, "width": 80, "height": 24} then a [time, "o", data] line per frame
+fn to_asciicast(recording: &Recording) -> String {
+    let mut res = format!("{{\"version\": 2, \"width\": {}, \"height\": {}}}\n", recording.width.max(1), recording.height.max(1));
+    for (time, data) in &recording.events {
+        res.push_str(&format!("[{:.3}, \"o\", {}]\n", time, json_string(data)));
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ansi() {
+        let picture = Picture::new("#.\n.#", |c| if c == '#' { Color::Red } else { Color::Default });
+        assert_eq!("\x1b[31m#\x1b[39m.\x1b[K\n.\x1b[31m#\x1b[0m\x1b[K\n", to_ansi(&picture.rows()));
+    }
+
+    #[test]
+    fn asciicast() {
+        let recording = Recording {
+            frames: 2,
+            width: 2,
+            height: 1,
+            events: vec![(0.0, "\x1b[Hab\r\n".to_string()), (0.5, "\x1b[H\"c".to_string())],
+        };
+        assert_eq!(r#"{"version": 2, "width": 2, "height": 1}
+[0.000, "o", "\u001b[Hab\u000d\n"]
+[0.500, "o", "\u001b[H\"c"]
+"#, to_asciicast(&recording));
+    }
+}
@@ -0,0 +1,54 @@
+use aoc2019::intcode::{self, Computer, ComputerState, Engine, Interpreter, Program, Run, Stepper, Stream, Value};
+use aoc2019::{conformance, fuzz};
+use std::path::Path;
+use std::rc::Rc;
+
+// Outputs its input doubled until the input runs out
+const DOUBLER: &str = "3,9,1002,9,2,9,4,9,1105,1,0";
+
+#[test]
+fn computer_with_streams() {
+    let mut comp = Computer::new(intcode::parse_program(DOUBLER).unwrap());
+    let input = Stream::new_wrapped();
+    let output = Stream::new_wrapped();
+    comp.set_input(Some(Rc::clone(&input)));
+    comp.set_output(Some(Rc::clone(&output)));
+
+    input.borrow_mut().write_all(&[1, 2, 3]);
+    assert_eq!(ComputerState::WaitingOnInput, comp.execute());
+    assert_eq!(vec![2, 4, 6], output.borrow_mut().read_all());
+
+    input.borrow_mut().write(21);
+    assert_eq!(ComputerState::WaitingOnInput, comp.execute());
+    assert_eq!(Some(42), output.borrow_mut().read());
+}
+
+#[test]
+fn engine() {
+    let run = Interpreter.run(&intcode::parse_program(DOUBLER).unwrap(), &[5], 1000);
+    assert_eq!(vec![10], run.output);
+    assert_eq!(Some(ComputerState::WaitingOnInput), run.state);
+    assert_eq!(None, Interpreter.run(&intcode::parse_program(DOUBLER).unwrap(), &[5], 3).state);
+    assert_eq!(run, Stepper.run(&intcode::parse_program(DOUBLER).unwrap(), &[5], 1000));
+}
+
+// Another tool's engine, here just the interpreter under a different name
+struct Wrapped;
+
+impl Engine for Wrapped {
+    fn name(&self) -> &str {
+        "wrapped"
+    }
+
+    fn run(&self, program: &Program, input: &[Value], max_steps: usize) -> Run {
+        Interpreter.run(program, input, max_steps)
+    }
+}
+
+#[test]
+fn other_engines() {
+    let cases = conformance::load(Path::new(conformance::DIR)).unwrap();
+    assert!(!cases.is_empty());
+    assert!(conformance::run(&Wrapped, &cases).is_empty());
+    assert!(fuzz::fuzz(&[&Interpreter, &Wrapped], 0, 50).is_none());
+}
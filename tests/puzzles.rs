@@ -0,0 +1,64 @@
+use aoc2019::answer::Answer;
+use aoc2019::config::Config;
+use aoc2019::error::Error;
+use aoc2019::params::Params;
+use aoc2019::solution::{self, Source};
+use std::fs;
+use std::path::PathBuf;
+
+fn write_input(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("aoc2019-{}-{}", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn registry() {
+    assert_eq!(50, solution::all().len());
+    assert_eq!("Donut Maze", solution::find("20-1").unwrap().title());
+    assert!(solution::find("26-1").is_err());
+}
+
+#[test]
+fn solvers() {
+    let day1 = solution::find("1-1").unwrap();
+    assert_eq!(Answer::Integer(2 + 2 + 654 + 33583), day1.run("12\n14\n1969\n100756", &Config::default()).unwrap());
+    let day3 = solution::find("3-1").unwrap();
+    assert_eq!(Answer::Integer(6), day3.run("R8,U5,L5,D3\nU7,R6,D4,L4", &Config::default()).unwrap());
+}
+
+#[test]
+fn execute_from_file() {
+    let path = write_input("1.txt", "12\n14\n1969\n100756\n");
+    let answer = solution::execute(solution::find("1-2").unwrap(), &Source::File(path.clone()), &Config::default()).unwrap();
+    fs::remove_file(path).unwrap();
+    assert_eq!(Answer::Integer(2 + 2 + 966 + 50346), answer);
+    assert_eq!("51316", answer.to_string());
+}
+
+#[test]
+fn config() {
+    let dir = std::env::temp_dir().join(format!("aoc2019-{}-inputs", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("12.txt"), "<x=-1, y=0, z=2>\n<x=2, y=-10, z=-7>\n<x=4, y=-8, z=8>\n<x=3, y=5, z=-1>\n").unwrap();
+    let day12 = solution::find("12-1").unwrap();
+    let config = Config { params: Params::check(day12, &["steps=10"]).unwrap(), input_dir: Some(dir.clone()), ..Config::default() };
+    let answer = solution::execute(day12, &Source::InputDir, &config);
+    fs::remove_dir_all(dir).unwrap();
+    assert_eq!(Ok(Answer::Integer(179)), answer);
+}
+
+#[test]
+fn parse_errors() {
+    let path = write_input("6.txt", "COM)B\nB)C\nCOM\n");
+    let e = solution::execute(solution::find("6-1").unwrap(), &Source::File(path.clone()), &Config::default()).err().unwrap();
+    fs::remove_file(&path).unwrap();
+    match e {
+        Error::Parse { file, line, text, .. } => {
+            assert_eq!(Some(path.display().to_string()), file);
+            assert_eq!(3, line);
+            assert_eq!("COM", text);
+        }
+        e => panic!("Expected a parse error but got {:?}", e),
+    }
+}